@@ -1,12 +1,9 @@
-use std::{
-    path::{Path, PathBuf},
-    process,
-};
+use std::{path::Path, process};
 
 use std::io::Write;
 use termcolor::{ColorChoice, ColorSpec, StandardStream, WriteColor};
 
-use crate::util::add_from_youtube_link;
+use crate::util::{add_from_youtube_link, library};
 
 use super::data::{default_sound_multiplier, PlaylistInfo, Song};
 
@@ -22,6 +19,11 @@ pub struct Add {
     /// Add local music instead of download from youtube
     #[clap(short, long, action)]
     local: bool,
+
+    /// Add a youtube video with chapters as one song instead of one song per
+    /// chapter
+    #[clap(long, action)]
+    no_chapters: bool,
 }
 
 impl Add {
@@ -34,7 +36,7 @@ impl Add {
     }
 
     fn download_from_youtube(&self) {
-        if let Err(e) = add_from_youtube_link(&self.playlist_name, &self.link) {
+        if let Err(e) = add_from_youtube_link(&self.playlist_name, &self.link, !self.no_chapters) {
             println!("Something went wrong while downloading: {}", e);
         };
     }
@@ -53,15 +55,15 @@ impl Add {
         }
         .to_string_lossy();
 
-        let song = Song {
-            song_name: file_name.to_string(),
-            path_to_song: PathBuf::from(path),
-            author: None,
-            sound_multiplier: default_sound_multiplier(),
-        };
+        let song = Song::new(
+            file_name.to_string(),
+            library::to_stored(path),
+            None,
+            default_sound_multiplier(),
+        );
 
         let mut playlist_info = PlaylistInfo::load_or_create(&self.playlist_name);
         playlist_info.songs.push(song);
-        playlist_info.save();
+        playlist_info.save_or_exit();
     }
 }
@@ -0,0 +1,93 @@
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::{Args, Subcommand};
+
+use crate::util::{bundle, print_missing};
+
+use super::data::PlaylistInfo;
+
+#[derive(Args)]
+pub struct BundleCommand {
+    #[clap(subcommand)]
+    command: BundleSubcommand,
+}
+
+#[derive(Subcommand)]
+enum BundleSubcommand {
+    /// Pack a playlist and its audio files into a tar file
+    Export {
+        /// The playlist to pack
+        playlist_name: String,
+
+        /// The tar file to write
+        output: PathBuf,
+    },
+
+    /// Unpack a bundle into a new playlist. The audio files are put into the
+    /// downloads folder
+    Import {
+        /// The tar file to unpack
+        file: PathBuf,
+
+        /// The name of the new playlist. The name of the playlist in the
+        /// bundle is used if not given
+        #[clap(short, long)]
+        name: Option<String>,
+    },
+}
+
+impl BundleCommand {
+    pub fn handle(&self) {
+        match &self.command {
+            BundleSubcommand::Export {
+                playlist_name,
+                output,
+            } => Self::export(playlist_name, output),
+            BundleSubcommand::Import { file, name } => Self::import(file, name.as_deref()),
+        }
+    }
+
+    fn export(playlist_name: &str, output: &Path) {
+        let playlist_info = PlaylistInfo::load_or_exit(playlist_name);
+
+        let missing = match bundle::export(&playlist_info, output) {
+            Ok(v) => v,
+            Err(e) => {
+                println!("Failed to write {}! Error: {:#}", output.display(), e);
+                process::exit(1);
+            }
+        };
+
+        print_missing(
+            &missing,
+            "songs can't be found, their files are not in the bundle",
+        );
+
+        println!(
+            r#"Packed "{}" into {}"#,
+            playlist_info.name,
+            output.display()
+        );
+    }
+
+    fn import(file: &Path, name: Option<&str>) {
+        let mut playlist_info = match bundle::import(file, name) {
+            Ok(v) => v,
+            Err(e) => {
+                println!("Failed to import {}! Error: {:#}", file.display(), e);
+                process::exit(1);
+            }
+        };
+
+        playlist_info.save_or_exit();
+
+        println!(
+            r#"Imported {} songs into "{}""#,
+            playlist_info.songs.len(),
+            playlist_info.name
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use std::process;
+
+use clap::Args;
+
+use crate::util::playlist_names;
+
+use super::data::PlaylistInfo;
+
+#[derive(Args)]
+pub struct CopyPlaylist {
+    /// The playlist to copy
+    playlist_name: String,
+
+    /// The name of the copy
+    new_name: String,
+}
+
+impl CopyPlaylist {
+    pub fn handle(&self) {
+        if playlist_names()
+            .unwrap_or_default()
+            .contains(&self.new_name)
+        {
+            println!(r#"Playlist "{}" already exists!"#, self.new_name);
+            process::exit(1);
+        }
+
+        let playlist_info = PlaylistInfo::load_or_exit(&self.playlist_name);
+
+        // the copy has the same songs of the song library, so their
+        // statistics and tags are shared
+        let mut copy = PlaylistInfo::new(&self.new_name);
+        copy.songs = playlist_info.songs;
+        copy.save_or_exit();
+
+        println!(
+            r#"Copied {} songs from "{}" into "{}""#,
+            copy.songs.len(),
+            self.playlist_name,
+            self.new_name
+        );
+    }
+}
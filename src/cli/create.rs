@@ -1,3 +1,5 @@
+use std::process;
+
 use clap::Args;
 
 use crate::util::create_playlist;
@@ -10,7 +12,11 @@ pub struct Create {
 
 impl Create {
     pub fn handle(&self) {
-        create_playlist(&self.playlist_name);
+        if let Err(e) = create_playlist(&self.playlist_name) {
+            println!("Failed to create the playlist! Error: {:#}", e);
+            process::exit(1);
+        }
+
         println!("Created successfully!");
     }
 }
@@ -1,10 +1,22 @@
-use std::{fs, path::PathBuf, process};
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    path::PathBuf,
+    process, slice,
+    time::Duration,
+};
 
+use anyhow::bail;
 use basic_quick_lib::{io_util::input_trim, time::LocalTime};
 use chrono::Local;
 use serde::{Deserialize, Serialize};
 
-use crate::util::playlist_info_path;
+use crate::util::{
+    audio, backup, library, merge,
+    migration::{self, Versioned},
+    playlist_info_path,
+    song_library::{self, SongLibrary},
+    storage::{storage, StorageKind},
+};
 
 pub const fn default_sound_multiplier() -> f32 {
     1.0
@@ -23,10 +35,53 @@ pub struct PlaylistInfo {
 
     #[serde(skip)]
     pub folder_name: String,
+
+    /// Only set when this playlist is a filtered view of other playlists. It
+    /// says where each song is stored so changes are saved back into the
+    /// playlist that the song came from
+    #[serde(skip)]
+    pub sources: Option<Vec<SongSource>>,
+
+    /// The playlist as it was in `info.json` when it was last loaded or saved.
+    /// Used to find out what other processes changed when saving. It is read
+    /// through `read_info`, so its songs are written the same way as the songs
+    /// in memory even if the file has an older format
+    #[serde(skip)]
+    pub base: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SongSource {
+    pub folder_name: String,
+    pub index: usize,
+}
+
+/// How a playlist is written into `info.json`
+#[derive(Serialize, Deserialize)]
+struct StoredPlaylist {
+    #[serde(default)]
+    songs: Vec<SongRef>,
+
+    #[serde(default)]
+    created: Option<LocalTime>,
+}
+
+/// Songs are written as their id in the song library. Playlists from before
+/// the song library have the whole songs
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SongRef {
+    Id(String),
+    Song(Box<Song>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Song {
+    /// The id of the song in the song library. It is empty until the song is
+    /// saved for the first time
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+
     #[serde(default)]
     pub song_name: String,
 
@@ -38,6 +93,46 @@ pub struct Song {
 
     #[serde(default = "default_sound_multiplier")]
     pub sound_multiplier: f32,
+
+    /// How many times the song has been played until the end
+    #[serde(default)]
+    pub play_count: u32,
+
+    /// How many times the song has been skipped before it ended
+    #[serde(default)]
+    pub skip_count: u32,
+
+    #[serde(default)]
+    pub last_played: Option<LocalTime>,
+
+    #[serde(default)]
+    pub date_added: Option<LocalTime>,
+
+    /// From 1 to 5
+    #[serde(default)]
+    pub rating: Option<u8>,
+
+    #[serde(default)]
+    pub favorite: bool,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// The `.lrc` file of the song. If it is not set, the `.lrc` file next to
+    /// the song is used
+    #[serde(default)]
+    pub lyrics_path: Option<PathBuf>,
+
+    /// Seconds into the audio file where the song starts. Used to skip long
+    /// intros, and when several songs share one file, like the chapters of a
+    /// youtube video
+    #[serde(default)]
+    pub start_at: Option<f64>,
+
+    /// Seconds into the audio file where the song ends. The player moves on to
+    /// the next song once it is reached
+    #[serde(default)]
+    pub end_at: Option<f64>,
 }
 
 impl PlaylistInfo {
@@ -47,31 +142,430 @@ impl PlaylistInfo {
             songs: Vec::new(),
             created: Some(LocalTime(Local::now())),
             folder_name: playlist_name.to_string(),
+            sources: None,
+            base: None,
         }
     }
 
     pub fn load(playlist_name: &str) -> anyhow::Result<Self> {
-        let path = playlist_info_path(playlist_name);
-        let data = fs::read_to_string(&path)?;
-        let mut this: PlaylistInfo = serde_json::from_str(&data)?;
+        Self::move_songs_to_library(playlist_name)?;
 
-        let paths = path.iter().collect::<Vec<_>>();
-        let name = paths[paths.len() - 2].to_str().unwrap();
+        let json = Self::read_info(playlist_name)?;
+        let mut this: PlaylistInfo = serde_json::from_value(json.clone())?;
+        this.base = Some(json);
 
-        this.name = name.to_string();
+        this.name = playlist_name.to_string();
         this.folder_name = playlist_name.to_string();
 
         Ok(this)
     }
 
-    pub fn save(&self) {
-        let json_string = serde_json::to_string_pretty(self).unwrap();
-        let info_path = playlist_info_path(&self.folder_name);
-        let mut path = info_path.clone();
-        path.pop();
+    /// create a view of the playlist that only contains the songs that
+    /// matches the predicate. Saving the view saves the changes back into this
+    /// playlist
+    pub fn filtered(&self, predicate: impl Fn(&Song) -> bool) -> Self {
+        Self {
+            created: self.created.clone(),
+            folder_name: self.folder_name.clone(),
+            ..Self::view_of(&self.name, slice::from_ref(self), predicate)
+        }
+    }
+
+    /// create a view over several playlists that contains every song that
+    /// matches the predicate. Saving it finds each song in the playlist it
+    /// came from, so the playlists can be changed while the view is open
+    pub fn view_of(
+        name: &str,
+        playlists: &[PlaylistInfo],
+        predicate: impl Fn(&Song) -> bool,
+    ) -> Self {
+        let (sources, songs) = playlists
+            .iter()
+            .flat_map(|playlist_info| {
+                playlist_info
+                    .songs
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, song)| (&playlist_info.folder_name, index, song))
+            })
+            .filter(|(_, _, song)| predicate(song))
+            .map(|(folder_name, index, song)| {
+                let source = SongSource {
+                    folder_name: folder_name.clone(),
+                    index,
+                };
+
+                (source, song.clone())
+            })
+            .unzip();
+
+        Self {
+            name: name.to_string(),
+            songs,
+            created: None,
+            folder_name: name.to_string(),
+            sources: Some(sources),
+            base: None,
+        }
+    }
+
+    /// remove the song at index, from the playlist it is stored in as well if
+    /// this is a view
+    pub fn remove_song(&mut self, index: usize) -> anyhow::Result<()> {
+        self.remove_songs(&[index])
+    }
+
+    /// remove the songs at the indices, from the playlists they are stored in
+    /// as well if this is a view. Every playlist is saved once
+    pub fn remove_songs(&mut self, indices: &[usize]) -> anyhow::Result<()> {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        indices.dedup();
+
+        let mut removed_sources = Vec::new();
+
+        for index in indices {
+            let song = self.songs.remove(index);
+
+            let sources = match &mut self.sources {
+                Some(sources) => sources,
+                None => continue,
+            };
+
+            let removed = sources.remove(index);
+
+            // songs that were after the removed one are moved forward by one
+            for source in sources.iter_mut() {
+                if source.folder_name == removed.folder_name && source.index > removed.index {
+                    source.index -= 1;
+                }
+            }
+
+            removed_sources.push((removed, song));
+        }
+
+        // the indices are removed in the same order, since each one is
+        // counted after the ones before it were removed
+        let mut playlists = BTreeMap::new();
+
+        for (removed, song) in removed_sources {
+            let playlist_info = match playlists.entry(removed.folder_name.clone()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => match PlaylistInfo::load(&removed.folder_name) {
+                    Ok(v) => entry.insert(v),
+                    Err(_) => continue,
+                },
+            };
+
+            match find_song(&playlist_info.songs, &song, removed.index) {
+                Some(index) => {
+                    playlist_info.songs.remove(index);
+                }
+                None => Self::not_in_source(&song, &removed),
+            }
+        }
+
+        for playlist_info in playlists.values_mut() {
+            playlist_info.save()?;
+        }
+
+        self.save()
+    }
+
+    /// move the song at `from` so it ends up at `to`. A view keeps where each
+    /// song is stored, the playlists it was made of keep their order
+    pub fn move_song(&mut self, from: usize, to: usize) {
+        let song = self.songs.remove(from);
+        self.songs.insert(to, song);
+
+        if let Some(sources) = &mut self.sources {
+            let source = sources.remove(from);
+            sources.insert(to, source);
+        }
+    }
+
+    /// save the playlist into the storage. Changes made by other processes
+    /// since it was loaded are merged in first, and the playlist is updated to
+    /// what was saved
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        if let Some(sources) = &self.sources {
+            return self.save_to_sources(sources);
+        }
+
+        let _lock = storage().lock_playlist(&self.folder_name)?;
+
+        let mut library = SongLibrary::load()?;
+        for song in self.songs.iter_mut() {
+            Self::give_id(&self.name, song, &mut library);
+        }
+
+        let ours = serde_json::to_value(&*self)?;
+        let merged = match Self::read_info(&self.folder_name) {
+            Ok(disk) => merge::merge_playlist(self.base.as_ref(), &ours, &disk),
+            Err(_) => ours,
+        };
+
+        self.apply(merged)?;
+        SongLibrary::update(&self.songs)?;
+
+        Self::write_stored(&self.folder_name, &self.songs, &self.created)
+    }
+
+    /// merge in the changes other processes saved since the playlist was
+    /// loaded. Returns true if anything changed
+    pub fn reload(&mut self) -> anyhow::Result<bool> {
+        if self.sources.is_some() {
+            return Ok(false);
+        }
+
+        let _lock = storage().lock_playlist(&self.folder_name)?;
+
+        let disk = Self::read_info(&self.folder_name)?;
+
+        if self.base.as_ref() == Some(&disk) {
+            return Ok(false);
+        }
+
+        let merged =
+            merge::merge_playlist(self.base.as_ref(), &serde_json::to_value(&*self)?, &disk);
+        self.apply(merged)?;
+        self.base = Some(disk);
+
+        Ok(true)
+    }
+
+    /// the songs whose audio files can't be found
+    pub fn missing_songs(&self) -> Vec<&Song> {
+        self.songs
+            .iter()
+            .filter(|song| !song.path().exists())
+            .collect()
+    }
+
+    /// load the playlist, or exit the program if it failed
+    pub fn load_or_exit(playlist_name: &str) -> Self {
+        match Self::load(playlist_name) {
+            Ok(v) => v,
+            Err(err) => {
+                println!(
+                    r#"Failed to load playlist "{}"! Error: {}"#,
+                    playlist_name, err
+                );
+                process::exit(1);
+            }
+        }
+    }
+
+    /// save the playlist, or exit the program if it failed
+    pub fn save_or_exit(&mut self) {
+        if let Err(e) = self.save() {
+            println!("{}", self.failed("save", &e));
+            process::exit(1);
+        }
+    }
+
+    /// the message shown when doing something with the playlist failed, like
+    /// `Failed to save playlist "name"! Error: ...`
+    pub fn failed(&self, action: &str, e: &anyhow::Error) -> String {
+        format!(
+            r#"Failed to {} playlist "{}"! Error: {:#}"#,
+            action, self.name, e
+        )
+    }
+
+    /// replace the playlist with its nth newest backup. The current one
+    /// becomes the newest backup, so restoring can be undone. Backups are
+    /// only kept for the json files
+    pub fn restore(playlist_name: &str, backup: usize) -> anyhow::Result<()> {
+        if storage().kind() != StorageKind::Json {
+            bail!("Backups are only kept when the playlists are stored in json files");
+        }
+
+        let _lock = storage().lock_playlist(playlist_name)?;
+
+        let data = backup::read_backup(&playlist_info_path(playlist_name), backup)?;
+
+        // make sure the backup is a playlist before replacing anything
+        let json: serde_json::Value = serde_json::from_slice(&data)?;
+        serde_json::from_value::<StoredPlaylist>(migration::migrate_playlist(json.clone())?)?;
+
+        storage().write_playlist(playlist_name, &json)
+    }
+
+    /// read the playlist with the songs taken from the song library
+    fn read_info(playlist_name: &str) -> anyhow::Result<serde_json::Value> {
+        let json = migration::migrate_playlist(storage().read_playlist(playlist_name)?)?;
+        let stored: StoredPlaylist = serde_json::from_value(json)?;
+
+        let library = SongLibrary::load()?;
+        let songs = stored
+            .songs
+            .into_iter()
+            .map(|song| match song {
+                SongRef::Id(id) => library.song(&id).cloned(),
+                SongRef::Song(song) => Ok(*song),
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(serde_json::to_value(PlaylistInfo {
+            songs,
+            created: stored.created,
+            ..Default::default()
+        })?)
+    }
+
+    /// move the songs of a playlist from before the song library into the
+    /// library, so the playlist only has their ids
+    fn move_songs_to_library(playlist_name: &str) -> anyhow::Result<()> {
+        let read = || -> anyhow::Result<StoredPlaylist> {
+            let json = migration::migrate_playlist(storage().read_playlist(playlist_name)?)?;
+
+            Ok(serde_json::from_value(json)?)
+        };
+        let is_moved = |stored: &StoredPlaylist| {
+            stored
+                .songs
+                .iter()
+                .all(|song| matches!(song, SongRef::Id(_)))
+        };
+
+        // only lock when there is something to move
+        if is_moved(&read()?) {
+            return Ok(());
+        }
+
+        let _lock = storage().lock_playlist(playlist_name)?;
+        let stored = read()?;
+
+        if is_moved(&stored) {
+            return Ok(());
+        }
+
+        let mut library = SongLibrary::load()?;
+        let mut songs = Vec::new();
+
+        for song in stored.songs {
+            match song {
+                SongRef::Id(id) => songs.push(library.song(&id)?.clone()),
+                SongRef::Song(mut song) => {
+                    Self::give_id(playlist_name, &mut song, &mut library);
+                    songs.push(*song);
+                }
+            }
+        }
+
+        SongLibrary::update(&songs)?;
+
+        Self::write_stored(playlist_name, &songs, &stored.created)
+    }
+
+    /// songs that are not in the library yet get an id. If the library
+    /// already has the same song, the song is merged into it. Songs that were
+    /// customized differently are kept as songs of their own, so nothing of
+    /// either is lost
+    fn give_id(playlist_name: &str, song: &mut Song, library: &mut SongLibrary) {
+        if !song.id.is_empty() {
+            return;
+        }
+
+        let same_songs = library.find(song);
+
+        if let Some(stored) = same_songs
+            .iter()
+            .find(|stored| stored.conflicts_with(song).is_empty())
+        {
+            let mut merged = (*stored).clone();
+            merged.merge(song);
+
+            library.insert(merged.clone());
+            *song = merged;
+            return;
+        }
+
+        if let Some(stored) = same_songs.first() {
+            println!(
+                r#"Song "{}" of playlist "{}" is in the song library with a different {}, so it is kept as a song of its own"#,
+                song.song_name,
+                playlist_name,
+                stored.conflicts_with(song).join(", ")
+            );
+        }
+
+        song.id = song_library::new_id();
+        library.insert(song.clone());
+    }
+
+    /// write the playlist with the ids of the songs
+    fn write_stored(
+        playlist_name: &str,
+        songs: &[Song],
+        created: &Option<LocalTime>,
+    ) -> anyhow::Result<()> {
+        let stored = StoredPlaylist {
+            songs: songs
+                .iter()
+                .map(|song| SongRef::Id(song.id.clone()))
+                .collect(),
+            created: created.clone(),
+        };
+
+        let json = serde_json::to_value(Versioned {
+            version: migration::PLAYLIST_VERSION,
+            data: &stored,
+        })?;
+
+        storage().write_playlist(playlist_name, &json)
+    }
+
+    /// take the songs and the other saved fields from the json
+    fn apply(&mut self, json: serde_json::Value) -> anyhow::Result<()> {
+        let merged: PlaylistInfo = serde_json::from_value(json.clone())?;
+
+        self.songs = merged.songs;
+        self.created = merged.created;
+        self.base = Some(json);
 
-        fs::create_dir_all(&path).unwrap();
-        fs::write(&info_path, json_string).unwrap();
+        Ok(())
+    }
+
+    fn save_to_sources(&self, sources: &[SongSource]) -> anyhow::Result<()> {
+        let mut folder_names = sources
+            .iter()
+            .map(|source| source.folder_name.as_str())
+            .collect::<Vec<_>>();
+        folder_names.sort_unstable();
+        folder_names.dedup();
+
+        for folder_name in folder_names {
+            let mut playlist_info = match PlaylistInfo::load(folder_name) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+
+            for (song, source) in self.songs.iter().zip(sources) {
+                if source.folder_name != folder_name {
+                    continue;
+                }
+
+                match find_song(&playlist_info.songs, song, source.index) {
+                    Some(index) => playlist_info.songs[index] = song.clone(),
+                    None => Self::not_in_source(song, source),
+                }
+            }
+
+            playlist_info.save()?;
+        }
+
+        Ok(())
+    }
+
+    /// the song of a view was removed from the playlist it came from by
+    /// another process, so the change to it is not saved
+    fn not_in_source(song: &Song, source: &SongSource) {
+        println!(
+            r#"Skipped "{}", it is not in playlist "{}" anymore"#,
+            song.song_name, source.folder_name
+        );
     }
 
     pub fn load_or_create(playlist_name: &str) -> Self {
@@ -112,8 +606,155 @@ impl Song {
             path_to_song: path,
             author,
             sound_multiplier,
+            date_added: Some(LocalTime(Local::now())),
+            ..Default::default()
+        }
+    }
+
+    /// add the tag to the song. Tags are not case sensitive. Returns false if
+    /// the song already has the tag
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        let tag = normalize_tag(tag);
+
+        if tag.is_empty() || self.has_tag(&tag) {
+            return false;
+        }
+
+        self.tags.push(tag);
+        true
+    }
+
+    /// returns false if the song does not have the tag
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let tag = normalize_tag(tag);
+        let len = self.tags.len();
+        self.tags.retain(|t| normalize_tag(t) != tag);
+
+        len != self.tags.len()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = normalize_tag(tag);
+        self.tags.iter().any(|t| normalize_tag(t) == tag)
+    }
+
+    /// songs are the same song if they have the same id in the song library,
+    /// or if they play the same part of the same file when one has no id yet
+    pub fn is_same(&self, other: &Song) -> bool {
+        if !self.id.is_empty() && !other.id.is_empty() {
+            return self.id == other.id;
         }
+
+        self.path_to_song == other.path_to_song && self.start_at == other.start_at
+    }
+
+    /// the path of the audio file. Relative paths are relative to the
+    /// library root
+    pub fn path(&self) -> PathBuf {
+        library::resolve(&self.path_to_song)
     }
+
+    /// how long the song plays for, counting its start and end offsets
+    pub fn duration(&self) -> Option<Duration> {
+        let length = audio::audio_length(&self.path())?;
+        let end = self.end_offset().unwrap_or(length).min(length);
+
+        Some(end.saturating_sub(self.start_offset()))
+    }
+
+    pub fn start_offset(&self) -> Duration {
+        Duration::from_secs_f64(self.start_at.unwrap_or_default().max(0.0))
+    }
+
+    pub fn end_offset(&self) -> Option<Duration> {
+        self.end_at.map(|end| Duration::from_secs_f64(end.max(0.0)))
+    }
+
+    /// update the statistics after the song is played or skipped
+    pub fn record_play(&mut self, played_at: LocalTime, skipped: bool) {
+        if skipped {
+            self.skip_count += 1;
+        } else {
+            self.play_count += 1;
+        }
+
+        self.last_played = Some(played_at);
+    }
+
+    /// the customizations that are set in both songs but are not the same.
+    /// Such songs can't be merged without losing one of them
+    pub fn conflicts_with(&self, other: &Song) -> Vec<&'static str> {
+        fn differ<T: PartialEq>(a: &Option<T>, b: &Option<T>) -> bool {
+            matches!((a, b), (Some(a), Some(b)) if a != b)
+        }
+
+        let mut fields = Vec::new();
+
+        if self.song_name != other.song_name {
+            fields.push("name");
+        }
+        if differ(&self.author, &other.author) {
+            fields.push("author");
+        }
+        if self.sound_multiplier != other.sound_multiplier {
+            fields.push("multiplier");
+        }
+        if differ(&self.rating, &other.rating) {
+            fields.push("rating");
+        }
+        if differ(&self.lyrics_path, &other.lyrics_path) {
+            fields.push("lyrics");
+        }
+
+        fields
+    }
+
+    /// add what was recorded for the other song, which plays the same thing.
+    /// The counts are added up, the tags are joined, and what is only set in
+    /// the other song is taken
+    pub fn merge(&mut self, other: &Song) {
+        self.play_count += other.play_count;
+        self.skip_count += other.skip_count;
+        self.favorite |= other.favorite;
+        self.rating = self.rating.max(other.rating);
+
+        if other.last_played.as_ref().map(|t| t.0) > self.last_played.as_ref().map(|t| t.0) {
+            self.last_played = other.last_played.clone();
+        }
+
+        if let Some(added) = &other.date_added {
+            if self.date_added.as_ref().is_none_or(|t| added.0 < t.0) {
+                self.date_added = Some(added.clone());
+            }
+        }
+
+        if self.author.is_none() {
+            self.author = other.author.clone();
+        }
+        if self.lyrics_path.is_none() {
+            self.lyrics_path = other.lyrics_path.clone();
+        }
+
+        for tag in &other.tags {
+            self.add_tag(tag);
+        }
+    }
+}
+
+/// where the song is in the songs now. It is looked for if it is not at
+/// `index` anymore, since the songs might have been sorted or changed since
+pub fn find_song(songs: &[Song], song: &Song, index: usize) -> Option<usize> {
+    if songs.get(index).is_some_and(|stored| stored.is_same(song)) {
+        return Some(index);
+    }
+
+    songs.iter().position(|stored| stored.is_same(song))
+}
+
+/// how a tag is stored and compared. Tags are not case sensitive, in every
+/// language
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
 }
 
 #[cfg(test)]
@@ -123,7 +764,11 @@ mod test {
     use basic_quick_lib::time::LocalTime;
     use chrono::Local;
 
-    use super::{PlaylistInfo, Song};
+    use serde_json::json;
+
+    use crate::util::{migration::PLAYLIST_VERSION, storage::storage};
+
+    use super::{PlaylistInfo, Song, SongSource};
 
     #[test]
     fn playlist_info_parse() {
@@ -134,9 +779,12 @@ mod test {
                 path_to_song: PathBuf::from_iter([r"C:\", "test", "what"]),
                 author: Some("Lucas Fan".to_string()),
                 sound_multiplier: 2.0,
+                ..Default::default()
             }],
             created: Some(LocalTime(Local::now())),
             folder_name: "test".to_string(),
+            sources: None,
+            base: None,
         };
 
         let json_string = serde_json::to_string_pretty(&info).unwrap_or_default();
@@ -149,18 +797,301 @@ mod test {
 
     #[test]
     fn write() {
-        let info = PlaylistInfo {
+        let mut info = PlaylistInfo {
             name: "a playlist".to_string(),
             songs: vec![Song {
                 song_name: "song1".to_string(),
                 path_to_song: PathBuf::from_iter([r"C:\", "test", "what"]),
                 author: Some("Lucas Fan".to_string()),
                 sound_multiplier: 2.0,
+                ..Default::default()
             }],
             created: Some(LocalTime(Local::now())),
             folder_name: "test".to_string(),
+            sources: None,
+            base: None,
+        };
+
+        info.save().unwrap();
+    }
+
+    #[test]
+    fn song_without_stats_loads() {
+        let song: Song = serde_json::from_str(
+            r#"{ "song_name": "song1", "path_to_song": "song1.wav", "author": null }"#,
+        )
+        .unwrap();
+
+        assert_eq!(song.sound_multiplier, 1.0);
+        assert_eq!(song.play_count, 0);
+        assert!(song.last_played.is_none());
+        assert!(song.date_added.is_none());
+    }
+
+    #[test]
+    fn record_play() {
+        let mut song = Song::new("song1".to_string(), PathBuf::from("song1.wav"), None, 1.0);
+
+        song.record_play(LocalTime(Local::now()), false);
+        song.record_play(LocalTime(Local::now()), true);
+        song.record_play(LocalTime(Local::now()), false);
+
+        assert_eq!(song.play_count, 2);
+        assert_eq!(song.skip_count, 1);
+        assert!(song.last_played.is_some());
+    }
+
+    #[test]
+    fn filtered_keeps_sources() {
+        let mut info = PlaylistInfo::new("test");
+        for (name, favorite) in [("a", true), ("b", false), ("c", true)] {
+            let mut song = Song::new(name.to_string(), PathBuf::from(name), None, 1.0);
+            song.favorite = favorite;
+            info.songs.push(song);
+        }
+
+        let view = info.filtered(|song| song.favorite);
+        let sources = view.sources.unwrap();
+
+        assert_eq!(view.songs.len(), 2);
+        assert_eq!(view.songs[1].song_name, "c");
+        assert_eq!(
+            sources[1],
+            SongSource {
+                folder_name: "test".to_string(),
+                index: 2
+            }
+        );
+    }
+
+    #[test]
+    fn view_saves_to_songs_moved_since() -> anyhow::Result<()> {
+        let mut info = PlaylistInfo::new("view_saves_to_songs_moved_since");
+        for name in ["a", "b", "c"] {
+            let mut song = Song::new(name.to_string(), PathBuf::from(name), None, 1.0);
+            song.favorite = name != "b";
+            info.songs.push(song);
+        }
+        info.save()?;
+
+        let mut view = PlaylistInfo::load(&info.name)?.filtered(|song| song.favorite);
+
+        // sorted by another process after the view was made
+        info.move_song(2, 0);
+        info.save()?;
+
+        let names = || -> anyhow::Result<Vec<(String, Option<u8>)>> {
+            Ok(PlaylistInfo::load(&info.name)?
+                .songs
+                .into_iter()
+                .map(|song| (song.song_name, song.rating))
+                .collect())
         };
 
-        info.save();
+        view.songs[1].rating = Some(5);
+        view.save()?;
+        assert_eq!(
+            names()?,
+            [
+                ("c".to_string(), Some(5)),
+                ("a".to_string(), None),
+                ("b".to_string(), None)
+            ]
+        );
+
+        view.remove_song(0)?;
+        assert_eq!(
+            names()?,
+            [("c".to_string(), Some(5)), ("b".to_string(), None)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn tag_view_saves_to_every_playlist() -> anyhow::Result<()> {
+        let mut playlists = Vec::new();
+        for (name, songs) in [("tag_view_a", ["x", "y"]), ("tag_view_b", ["z", "w"])] {
+            let mut info = PlaylistInfo::new(name);
+            for song_name in songs {
+                let path = PathBuf::from(name).join(song_name);
+                let mut song = Song::new(song_name.to_string(), path, None, 1.0);
+                if song_name != "y" {
+                    song.add_tag("rock");
+                }
+                info.songs.push(song);
+            }
+            info.save()?;
+            playlists.push(info);
+        }
+
+        let mut view = PlaylistInfo::view_of("#rock", &playlists, |song| song.has_tag("rock"));
+
+        // another process added a song in front of "x" and removed "z"
+        let added = Song::new("v".to_string(), PathBuf::from("v"), None, 1.0);
+        playlists[0].songs.insert(0, added);
+        playlists[0].save()?;
+        playlists[1].remove_song(0)?;
+
+        for song in view.songs.iter_mut() {
+            song.rating = Some(5);
+        }
+        view.save()?;
+
+        let rated = |name: &str| -> anyhow::Result<Vec<(String, Option<u8>)>> {
+            Ok(PlaylistInfo::load(name)?
+                .songs
+                .into_iter()
+                .map(|song| (song.song_name, song.rating))
+                .collect())
+        };
+
+        assert_eq!(
+            rated("tag_view_a")?,
+            [
+                ("v".to_string(), None),
+                ("x".to_string(), Some(5)),
+                ("y".to_string(), None)
+            ]
+        );
+        assert_eq!(rated("tag_view_b")?, [("w".to_string(), Some(5))]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn move_song() {
+        let mut info = PlaylistInfo::new("test");
+        for name in ["a", "b", "c", "d"] {
+            info.songs
+                .push(Song::new(name.to_string(), PathBuf::from(name), None, 1.0));
+        }
+
+        info.move_song(0, 2);
+        info.move_song(3, 0);
+
+        let names = info.songs.iter().map(|song| song.song_name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), ["d", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn tags() {
+        let mut song = Song::new("song1".to_string(), PathBuf::from("song1.wav"), None, 1.0);
+
+        assert!(song.add_tag("Focus"));
+        assert!(!song.add_tag("focus "));
+        assert!(song.add_tag("workout"));
+        assert!(song.has_tag("FOCUS"));
+        // not only ascii letters have a case
+        assert!(song.add_tag("Ärger"));
+        assert!(song.has_tag("ÄRGER"));
+        assert!(song.remove_tag(" ärger"));
+
+        assert!(song.remove_tag("focus"));
+        assert!(!song.remove_tag("focus"));
+        assert_eq!(song.tags, vec!["workout".to_string()]);
+    }
+
+    /// write a playlist from before the song library, which has whole songs
+    fn write_old_playlist(name: &str, songs: serde_json::Value) {
+        storage()
+            .write_playlist(name, &json!({ "version": 2, "songs": songs }))
+            .unwrap();
+    }
+
+    #[test]
+    fn old_playlists_merge_changes_of_both_processes() {
+        write_old_playlist(
+            "old-merge",
+            json!([
+                { "song_name": "a", "path_to_song": "old_merge/a.wav" },
+                { "song_name": "b", "path_to_song": "old_merge/b.wav" }
+            ]),
+        );
+
+        let mut first = PlaylistInfo::load("old-merge").unwrap();
+        let mut second = PlaylistInfo::load("old-merge").unwrap();
+
+        second.songs[0].song_name = "renamed".to_string();
+        second.save().unwrap();
+        first.songs[1].rating = Some(5);
+        first.save().unwrap();
+
+        let songs = PlaylistInfo::load("old-merge").unwrap().songs;
+        assert_eq!(songs[0].song_name, "renamed");
+        assert_eq!(songs[1].rating, Some(5));
+    }
+
+    #[test]
+    fn old_playlists_sharing_a_file_are_merged() {
+        let path = "old_playlists_sharing_a_file_are_merged/song.wav";
+
+        write_old_playlist(
+            "merged-a",
+            json!([{ "song_name": "song", "path_to_song": path, "play_count": 2, "tags": ["focus"] }]),
+        );
+        write_old_playlist(
+            "merged-b",
+            json!([{
+                "song_name": "song", "path_to_song": path, "play_count": 3, "skip_count": 1,
+                "favorite": true, "rating": 4, "tags": ["chill"]
+            }]),
+        );
+
+        let a = PlaylistInfo::load("merged-a").unwrap();
+        let b = PlaylistInfo::load("merged-b").unwrap();
+        // a was moved into the library before b, so it is read again
+        let a = PlaylistInfo::load(&a.name).unwrap();
+
+        let (a, b) = (&a.songs[0], &b.songs[0]);
+        assert_eq!(a.id, b.id);
+
+        for song in [a, b] {
+            assert_eq!(song.play_count, 5);
+            assert_eq!(song.skip_count, 1);
+            assert!(song.favorite);
+            assert_eq!(song.rating, Some(4));
+            assert_eq!(song.tags, ["focus", "chill"]);
+        }
+    }
+
+    #[test]
+    fn old_songs_customized_differently_are_kept_apart() {
+        let path = "old_songs_customized_differently_are_kept_apart/song.wav";
+
+        write_old_playlist(
+            "apart-a",
+            json!([{ "song_name": "song", "path_to_song": path, "play_count": 2 }]),
+        );
+        write_old_playlist(
+            "apart-b",
+            json!([{
+                "song_name": "renamed", "path_to_song": path, "sound_multiplier": 1.5,
+                "play_count": 3
+            }]),
+        );
+
+        let a = PlaylistInfo::load("apart-a").unwrap();
+        let b = PlaylistInfo::load("apart-b").unwrap();
+        let a = PlaylistInfo::load(&a.name).unwrap();
+
+        let (a, b) = (&a.songs[0], &b.songs[0]);
+        assert_ne!(a.id, b.id);
+        assert_eq!((a.song_name.as_str(), a.play_count), ("song", 2));
+        assert_eq!((b.song_name.as_str(), b.play_count), ("renamed", 3));
+        assert_eq!(b.sound_multiplier, 1.5);
+    }
+
+    #[test]
+    fn song_missing_from_the_library_fails_to_load() {
+        storage()
+            .write_playlist(
+                "missing-song",
+                &json!({ "version": PLAYLIST_VERSION, "songs": ["not-in-the-library"] }),
+            )
+            .unwrap();
+
+        let error = PlaylistInfo::load("missing-song").unwrap_err();
+        assert!(error.to_string().contains("not-in-the-library"));
     }
 }
@@ -0,0 +1,128 @@
+use std::process;
+
+use basic_quick_lib::io_util::input_trim;
+use clap::Args;
+
+use crate::util::{
+    dedupe::{find_duplicates, merge_duplicates},
+    playlist_names,
+};
+
+use super::data::PlaylistInfo;
+
+#[derive(Args)]
+pub struct Dedupe {
+    /// The playlist to remove the duplicates from
+    #[clap(required_unless_present = "all", conflicts_with = "all")]
+    playlist_name: Option<String>,
+
+    /// Look for duplicates across every playlist
+    #[clap(short, long, action)]
+    all: bool,
+
+    /// Remove every duplicate without asking
+    #[clap(short, long, action)]
+    yes: bool,
+}
+
+impl Dedupe {
+    pub fn handle(&self) {
+        let mut playlist_info = match self.load() {
+            Ok(v) => v,
+            Err(err) => {
+                println!("Failed to load the playlists! Error: {}", err);
+                process::exit(1);
+            }
+        };
+
+        let groups = find_duplicates(&playlist_info.songs);
+
+        if groups.is_empty() {
+            println!("No duplicates found!");
+            return;
+        }
+
+        let mut removed = Vec::new();
+
+        for group in groups {
+            let (&kept, duplicates) = group.split_first().expect("groups have several songs");
+
+            println!("Keeping:");
+            self.print_song(&playlist_info, kept);
+            println!("Duplicates:");
+
+            for &index in duplicates {
+                self.print_song(&playlist_info, index);
+            }
+
+            if !self.yes {
+                let input = input_trim("Remove the duplicates? (y/N): ");
+
+                if input.to_lowercase() != "y" {
+                    println!();
+                    continue;
+                }
+            }
+
+            println!();
+
+            let duplicate_songs = duplicates
+                .iter()
+                .map(|&index| playlist_info.songs[index].clone())
+                .collect::<Vec<_>>();
+            merge_duplicates(
+                &mut playlist_info.songs[kept],
+                &duplicate_songs.iter().collect::<Vec<_>>(),
+            );
+
+            removed.extend_from_slice(duplicates);
+        }
+
+        if removed.is_empty() {
+            println!("Nothing was removed");
+            return;
+        }
+
+        if let Err(e) = playlist_info.remove_songs(&removed) {
+            println!("Failed to remove the duplicates! Error: {:#}", e);
+            process::exit(1);
+        }
+
+        println!("Removed {} duplicates", removed.len());
+    }
+
+    /// the playlist, or a view of every playlist
+    fn load(&self) -> anyhow::Result<PlaylistInfo> {
+        match &self.playlist_name {
+            Some(name) => PlaylistInfo::load(name),
+            None => {
+                let playlists = playlist_names()?
+                    .iter()
+                    .filter_map(|name| PlaylistInfo::load(name).ok())
+                    .collect::<Vec<_>>();
+
+                Ok(PlaylistInfo::view_of("all", &playlists, |_| true))
+            }
+        }
+    }
+
+    fn print_song(&self, playlist_info: &PlaylistInfo, index: usize) {
+        let song = &playlist_info.songs[index];
+        let place = match &playlist_info.sources {
+            Some(sources) => format!(
+                "{} #{}",
+                sources[index].folder_name,
+                sources[index].index + 1
+            ),
+            None => format!("#{}", index + 1),
+        };
+
+        println!(
+            "    {} {} (x{}) {}",
+            place,
+            song.song_name,
+            song.sound_multiplier,
+            song.path().display()
+        );
+    }
+}
@@ -1,8 +1,6 @@
-use std::fs;
-
 use clap::Args;
 
-use crate::util::playlist_info_folder;
+use crate::util::storage::storage;
 
 #[derive(Args)]
 pub struct Delete {
@@ -12,9 +10,7 @@ pub struct Delete {
 
 impl Delete {
     pub fn handle(&self) {
-        let folder_path = playlist_info_folder(&self.playlist_name);
-
-        if let Err(e) = fs::remove_dir_all(folder_path) {
+        if let Err(e) = storage().delete_playlist(&self.playlist_name) {
             println!("Failed to remove playlist! Error: {}", e);
             return;
         }
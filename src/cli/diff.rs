@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+
+use clap::Args;
+use termcolor::ColorSpec;
+
+use crate::util::{colored, dedupe::path_key};
+
+use super::data::{PlaylistInfo, Song};
+
+#[derive(Args)]
+pub struct DiffPlaylists {
+    /// The first playlist to compare
+    first: String,
+
+    /// The second playlist to compare
+    second: String,
+}
+
+impl DiffPlaylists {
+    pub fn handle(&self) {
+        let first = PlaylistInfo::load_or_exit(&self.first);
+        let second = PlaylistInfo::load_or_exit(&self.second);
+
+        let only_in_first = only_in(&first.songs, &second.songs);
+        let only_in_second = only_in(&second.songs, &first.songs);
+
+        if only_in_first.is_empty() && only_in_second.is_empty() {
+            println!(
+                r#""{}" and "{}" have the same songs"#,
+                self.first, self.second
+            );
+            return;
+        }
+
+        Self::print_songs(&self.first, &first.songs, &only_in_first);
+        Self::print_songs(&self.second, &second.songs, &only_in_second);
+    }
+
+    fn print_songs(playlist_name: &str, songs: &[Song], indices: &[usize]) {
+        if indices.is_empty() {
+            return;
+        }
+
+        colored::writeln(
+            ColorSpec::new().set_bold(true),
+            &format!(r#"Only in "{}" ({} songs)"#, playlist_name, indices.len()),
+        );
+
+        for &index in indices {
+            let song = &songs[index];
+
+            match &song.author {
+                Some(author) => println!("{:>4}. {} - {}", index + 1, song.song_name, author),
+                None => println!("{:>4}. {}", index + 1, song.song_name),
+            }
+        }
+
+        println!();
+    }
+}
+
+/// the indices of the songs that play a part of a file that none of the
+/// other songs play
+fn only_in(songs: &[Song], others: &[Song]) -> Vec<usize> {
+    let others = others.iter().map(path_key).collect::<HashSet<_>>();
+
+    songs
+        .iter()
+        .enumerate()
+        .filter(|(_, song)| !others.contains(&path_key(song)))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::cli::data::Song;
+
+    use super::only_in;
+
+    fn song(path: &str, start_at: Option<f64>) -> Song {
+        Song {
+            start_at,
+            ..Song::new(path.to_string(), PathBuf::from(path), None, 1.0)
+        }
+    }
+
+    #[test]
+    fn songs_only_in_one_playlist() {
+        let first = [song("a", None), song("b", None), song("c", Some(30.0))];
+        let second = [song("b", None), song("c", None), song("d", None)];
+
+        assert_eq!(only_in(&first, &second), [0, 2]);
+        assert_eq!(only_in(&second, &first), [1, 2]);
+        assert!(only_in(&first, &first).is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::Args;
+
+use crate::util::{
+    playlist_file::{self, Entry, PlaylistFormat},
+    print_missing,
+};
+
+use super::data::{PlaylistInfo, Song};
+
+#[derive(Args)]
+pub struct Export {
+    /// The playlist to export
+    playlist_name: String,
+
+    /// The file to write the playlist into
+    output: PathBuf,
+
+    /// The format of the file. Can be either m3u, m3u8, xspf or pls. Guessed
+    /// from the extension of the output if not given
+    #[clap(short, long)]
+    format: Option<PlaylistFormat>,
+}
+
+impl Export {
+    pub fn handle(&self) {
+        let playlist_info = PlaylistInfo::load_or_exit(&self.playlist_name);
+
+        let format = match self.format {
+            Some(format) => format,
+            None => PlaylistFormat::from_path(&self.output).unwrap_or_else(|e| {
+                println!("{}", e);
+                process::exit(1);
+            }),
+        };
+
+        print_missing(
+            playlist_info.missing_songs(),
+            "songs can't be found, they are exported anyway",
+        );
+
+        let folder = self.output.parent().unwrap_or_else(|| Path::new(""));
+        let entries = playlist_info
+            .songs
+            .iter()
+            .map(|song| to_entry(song, folder))
+            .collect::<Vec<_>>();
+
+        if let Err(e) = fs::write(
+            &self.output,
+            playlist_file::write(format, &playlist_info.name, &entries),
+        ) {
+            println!("Failed to write {}! Error: {}", self.output.display(), e);
+            process::exit(1);
+        }
+
+        println!(
+            "Exported {} songs into {}",
+            entries.len(),
+            self.output.display()
+        );
+    }
+}
+
+/// songs inside the folder of the playlist file are written relative to it,
+/// so the folder can be moved to another device as a whole
+fn to_entry(song: &Song, folder: &Path) -> Entry {
+    let path = song.path();
+    let folder = fs::canonicalize(folder).unwrap_or_else(|_| folder.to_path_buf());
+
+    Entry {
+        path: path
+            .strip_prefix(&folder)
+            .map(|relative| relative.to_path_buf())
+            .unwrap_or(path.clone()),
+        title: Some(song.song_name.clone()),
+        author: song.author.clone(),
+        duration: song.duration(),
+    }
+}
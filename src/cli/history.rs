@@ -0,0 +1,130 @@
+use std::{fs, path::PathBuf, process};
+
+use chrono::NaiveDate;
+use clap::Args;
+use termcolor::ColorSpec;
+
+use crate::util::{
+    colored, format_duration,
+    history::{self, ExportFormat, PlayEvent},
+};
+
+#[derive(Args)]
+pub struct History {
+    /// Only show songs played on or after this date (YYYY-MM-DD)
+    #[clap(long)]
+    from: Option<NaiveDate>,
+
+    /// Only show songs played on or before this date (YYYY-MM-DD)
+    #[clap(long)]
+    to: Option<NaiveDate>,
+
+    /// Only show songs played from this playlist
+    #[clap(short, long)]
+    playlist: Option<String>,
+
+    /// Only show songs whose author contains this text (not case sensitive)
+    #[clap(short, long)]
+    author: Option<String>,
+
+    /// Export the history instead of printing it. Can be either csv or json
+    #[clap(short, long)]
+    export: Option<ExportFormat>,
+
+    /// The file to write the export into. Prints to the terminal if not given
+    #[clap(short, long, requires = "export")]
+    output: Option<PathBuf>,
+}
+
+impl History {
+    pub fn handle(&self) {
+        let events = match history::read_history() {
+            Ok(v) => v,
+            Err(e) => {
+                println!("Failed to read the listening history! Error: {}", e);
+                process::exit(1);
+            }
+        };
+
+        let events = events
+            .into_iter()
+            .filter(|event| self.matches(event))
+            .collect::<Vec<_>>();
+
+        match self.export {
+            Some(format) => self.export(&events, format),
+            None => Self::print(&events),
+        }
+    }
+
+    fn matches(&self, event: &PlayEvent) -> bool {
+        let date = event.started.naive_local().date();
+
+        if self.from.is_some_and(|from| date < from) || self.to.is_some_and(|to| date > to) {
+            return false;
+        }
+
+        if let Some(playlist) = &self.playlist {
+            if &event.playlist != playlist {
+                return false;
+            }
+        }
+
+        if let Some(author) = &self.author {
+            let author = author.to_lowercase();
+            let matched = event
+                .author
+                .as_ref()
+                .is_some_and(|a| a.to_lowercase().contains(&author));
+
+            if !matched {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn export(&self, events: &[PlayEvent], format: ExportFormat) {
+        let data = match format {
+            ExportFormat::Csv => history::to_csv(events),
+            ExportFormat::Json => serde_json::to_string_pretty(events).unwrap(),
+        };
+
+        match &self.output {
+            Some(path) => match fs::write(path, data) {
+                Ok(_) => println!("Exported {} entries to {}", events.len(), path.display()),
+                Err(e) => println!("Failed to write {}! Error: {}", path.display(), e),
+            },
+            None => println!("{}", data),
+        }
+    }
+
+    fn print(events: &[PlayEvent]) {
+        if events.is_empty() {
+            println!("Nothing has been played yet!");
+            return;
+        }
+
+        for event in events {
+            let song = match &event.author {
+                Some(author) => format!("{} - {}", event.song_name, author),
+                None => event.song_name.clone(),
+            };
+
+            print!(
+                "{} [{}] ",
+                event.started.format("%Y-%m-%d %H:%M"),
+                event.playlist
+            );
+            colored::write(ColorSpec::new().set_bold(true), &song);
+            print!(" ({})", format_duration(event.seconds_listened));
+
+            if event.skipped {
+                print!(" skipped");
+            }
+
+            println!();
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::Args;
+
+use crate::util::{
+    library,
+    playlist_file::{self, Entry, PlaylistFormat},
+    playlist_names, print_missing,
+};
+
+use super::data::{default_sound_multiplier, PlaylistInfo, Song};
+
+#[derive(Args)]
+pub struct Import {
+    /// The playlist file to import
+    file: PathBuf,
+
+    /// The name of the new playlist. The name of the file is used if not
+    /// given
+    #[clap(short, long)]
+    name: Option<String>,
+
+    /// The format of the file. Can be either m3u, m3u8, xspf or pls. Guessed
+    /// from the extension of the file if not given
+    #[clap(short, long)]
+    format: Option<PlaylistFormat>,
+}
+
+impl Import {
+    pub fn handle(&self) {
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => self
+                .file
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        };
+
+        if playlist_names().unwrap_or_default().contains(&name) {
+            println!(
+                r#"Playlist "{}" already exists! Use --name to import it under another name"#,
+                name
+            );
+            process::exit(1);
+        }
+
+        let format = match self.format {
+            Some(format) => format,
+            None => PlaylistFormat::from_path(&self.file).unwrap_or_else(|e| {
+                println!("{}", e);
+                process::exit(1);
+            }),
+        };
+
+        let entries = match Self::read_entries(&self.file, format) {
+            Ok(v) => v,
+            Err(e) => {
+                println!("Failed to read {}! Error: {:#}", self.file.display(), e);
+                process::exit(1);
+            }
+        };
+
+        let folder = self.file.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut playlist_info = PlaylistInfo::new(&name);
+        playlist_info.songs = entries
+            .into_iter()
+            .map(|entry| to_song(entry, folder))
+            .collect();
+
+        print_missing(
+            playlist_info.missing_songs(),
+            "songs can't be found, they are imported anyway",
+        );
+
+        playlist_info.save_or_exit();
+
+        println!(
+            r#"Imported {} songs into "{}""#,
+            playlist_info.songs.len(),
+            name
+        );
+    }
+
+    fn read_entries(file: &Path, format: PlaylistFormat) -> anyhow::Result<Vec<Entry>> {
+        let data = fs::read(file)?;
+
+        playlist_file::parse(format, &String::from_utf8_lossy(&data))
+    }
+}
+
+/// relative entries are relative to the folder of the playlist file
+fn to_song(entry: Entry, folder: &Path) -> Song {
+    let path = folder.join(&entry.path);
+
+    let song_name = entry.title.unwrap_or_else(|| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+
+    Song::new(
+        song_name,
+        library::to_stored(&path),
+        entry.author,
+        default_sound_multiplier(),
+    )
+}
@@ -0,0 +1,258 @@
+use std::{
+    cmp::Reverse, collections::HashSet, fmt::Display, fs, process, str::FromStr, time::Duration,
+};
+
+use anyhow::anyhow;
+use basic_quick_lib::time::LocalTime;
+use clap::Args;
+use serde::Serialize;
+use termcolor::ColorSpec;
+
+use crate::util::{colored, date_or, format_duration, format_size, playlist_names, sort_by};
+
+use super::data::PlaylistInfo;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortPlaylistsBy {
+    Name,
+    Songs,
+    Duration,
+    Created,
+    Size,
+    LastPlayed,
+}
+
+impl FromStr for SortPlaylistsBy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "name" => Ok(Self::Name),
+            "songs" => Ok(Self::Songs),
+            "duration" => Ok(Self::Duration),
+            "created" => Ok(Self::Created),
+            "size" => Ok(Self::Size),
+            "lastplayed" | "last-played" => Ok(Self::LastPlayed),
+            _ => Err(anyhow!(
+                "Unknown sort order! Valid ones are: name, songs, duration, created, size, last-played"
+            )),
+        }
+    }
+}
+
+impl Display for SortPlaylistsBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sort_by = match self {
+            Self::Name => "name",
+            Self::Songs => "songs",
+            Self::Duration => "duration",
+            Self::Created => "created",
+            Self::Size => "size",
+            Self::LastPlayed => "last-played",
+        };
+
+        write!(f, "{}", sort_by)
+    }
+}
+
+#[derive(Args)]
+pub struct List {
+    /// Sort the playlists. Names are sorted alphabetically, everything else
+    /// most or newest first. Can be either of name, songs, duration, created,
+    /// size, last-played
+    #[clap(short, long, default_value_t = SortPlaylistsBy::Name)]
+    sort: SortPlaylistsBy,
+
+    /// Reverse the order. Playlists without the value to sort by stay at the
+    /// end
+    #[clap(short, long, action)]
+    reverse: bool,
+
+    /// Print the playlists as json
+    #[clap(long, action)]
+    json: bool,
+}
+
+/// What is shown about a playlist
+#[derive(Serialize)]
+struct PlaylistSummary {
+    name: String,
+    songs: usize,
+    duration_secs: u64,
+    created: Option<LocalTime>,
+    /// The size of the audio files of the songs in bytes
+    size: u64,
+    last_played: Option<LocalTime>,
+}
+
+impl PlaylistSummary {
+    fn new(playlist_info: &PlaylistInfo) -> Self {
+        let duration = playlist_info
+            .songs
+            .iter()
+            .filter_map(|song| song.duration())
+            .sum::<Duration>();
+
+        // chapters of a video share the file, so every file is counted once
+        let size = playlist_info
+            .songs
+            .iter()
+            .map(|song| song.path())
+            .collect::<HashSet<_>>()
+            .iter()
+            .filter_map(|path| fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+
+        let last_played = playlist_info
+            .songs
+            .iter()
+            .filter_map(|song| song.last_played.as_ref())
+            .max_by_key(|time| time.0)
+            .cloned();
+
+        Self {
+            name: playlist_info.name.clone(),
+            songs: playlist_info.songs.len(),
+            duration_secs: duration.as_secs(),
+            created: playlist_info.created.clone(),
+            size,
+            last_played,
+        }
+    }
+}
+
+impl List {
+    pub fn handle(&self) {
+        let names = match playlist_names() {
+            Ok(v) => v,
+            Err(e) => {
+                println!("Failed to read the playlists! Error: {}", e);
+                process::exit(1);
+            }
+        };
+
+        let mut playlists = Vec::new();
+
+        for name in names {
+            match PlaylistInfo::load(&name) {
+                Ok(playlist_info) => playlists.push(PlaylistSummary::new(&playlist_info)),
+                // printed to stderr, so the json can still be read
+                Err(e) => eprintln!(r#"Failed to load playlist "{}"! Error: {}"#, name, e),
+            }
+        }
+
+        sort_playlists(&mut playlists, self.sort, self.reverse);
+
+        if self.json {
+            match serde_json::to_string_pretty(&playlists) {
+                Ok(json) => println!("{}", json),
+                Err(e) => {
+                    println!("Failed to write the json! Error: {}", e);
+                    process::exit(1);
+                }
+            }
+
+            return;
+        }
+
+        if playlists.is_empty() {
+            println!("There are no playlists yet! Use `create` to make one");
+            return;
+        }
+
+        Self::print_table(&playlists);
+    }
+
+    fn print_table(playlists: &[PlaylistSummary]) {
+        let name_width = playlists
+            .iter()
+            .map(|playlist| playlist.name.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max("Name".len());
+
+        colored::writeln(
+            ColorSpec::new().set_bold(true),
+            &format!(
+                "{:<name_width$}  {:>5}  {:>8}  {:<10}  {:>9}  {:<11}",
+                "Name", "Songs", "Duration", "Created", "Size", "Last played"
+            ),
+        );
+
+        for playlist in playlists {
+            println!(
+                "{:<name_width$}  {:>5}  {:>8}  {:<10}  {:>9}  {:<11}",
+                playlist.name,
+                playlist.songs,
+                format_duration(playlist.duration_secs),
+                date_or(&playlist.created, "unknown"),
+                format_size(playlist.size),
+                date_or(&playlist.last_played, "never"),
+            );
+        }
+    }
+}
+
+/// names go from a to z, everything else from the most to the least.
+/// `reverse` turns the order around, but playlists without the value always
+/// go to the end
+fn sort_playlists(playlists: &mut [PlaylistSummary], sort: SortPlaylistsBy, reverse: bool) {
+    let newest = |time: &Option<LocalTime>| time.as_ref().map(|time| Reverse(time.0));
+
+    match sort {
+        SortPlaylistsBy::Name => sort_by(playlists, reverse, |p| Some(p.name.to_lowercase())),
+        SortPlaylistsBy::Songs => sort_by(playlists, reverse, |p| Some(Reverse(p.songs))),
+        SortPlaylistsBy::Duration => {
+            sort_by(playlists, reverse, |p| Some(Reverse(p.duration_secs)))
+        }
+        SortPlaylistsBy::Created => sort_by(playlists, reverse, |p| newest(&p.created)),
+        SortPlaylistsBy::Size => sort_by(playlists, reverse, |p| Some(Reverse(p.size))),
+        SortPlaylistsBy::LastPlayed => sort_by(playlists, reverse, |p| newest(&p.last_played)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use basic_quick_lib::time::LocalTime;
+    use chrono::{Duration, Local};
+
+    use super::{sort_playlists, PlaylistSummary, SortPlaylistsBy};
+
+    fn playlists() -> Vec<PlaylistSummary> {
+        let now = Local::now();
+
+        [("b", 3, Some(1)), ("C", 1, None), ("a", 2, Some(2))]
+            .into_iter()
+            .map(|(name, songs, days_ago)| PlaylistSummary {
+                name: name.to_string(),
+                songs,
+                duration_secs: 0,
+                created: None,
+                size: 0,
+                last_played: days_ago.map(|days| LocalTime(now - Duration::days(days))),
+            })
+            .collect()
+    }
+
+    fn names(playlists: &[PlaylistSummary]) -> Vec<&str> {
+        playlists
+            .iter()
+            .map(|playlist| playlist.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn sort_playlists_by_field() {
+        let mut playlists = playlists();
+
+        sort_playlists(&mut playlists, SortPlaylistsBy::Name, false);
+        assert_eq!(names(&playlists), ["a", "b", "C"]);
+
+        sort_playlists(&mut playlists, SortPlaylistsBy::Songs, false);
+        assert_eq!(names(&playlists), ["b", "a", "C"]);
+
+        sort_playlists(&mut playlists, SortPlaylistsBy::LastPlayed, false);
+        assert_eq!(names(&playlists), ["b", "a", "C"]);
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::{Args, Subcommand};
+
+use crate::util::{get_index, library, lyrics::Lyrics};
+
+use super::data::PlaylistInfo;
+
+#[derive(Args)]
+pub struct LyricsCommand {
+    #[clap(subcommand)]
+    command: LyricsSubcommand,
+}
+
+#[derive(Subcommand)]
+enum LyricsSubcommand {
+    /// Attach a `.lrc` file to a song
+    Attach {
+        /// The playlist the song is in
+        playlist_name: String,
+
+        /// The index of the song. Negative value starts from the back
+        #[clap(allow_hyphen_values = true)]
+        index: i32,
+
+        /// The `.lrc` file
+        file: PathBuf,
+    },
+
+    /// Remove the attached `.lrc` file from a song. The `.lrc` file next to
+    /// the song will be used instead if there is one
+    Detach {
+        /// The playlist the song is in
+        playlist_name: String,
+
+        /// The index of the song. Negative value starts from the back
+        #[clap(allow_hyphen_values = true)]
+        index: i32,
+    },
+}
+
+impl LyricsCommand {
+    pub fn handle(&self) {
+        match &self.command {
+            LyricsSubcommand::Attach {
+                playlist_name,
+                index,
+                file,
+            } => Self::attach(playlist_name, *index, Some(file)),
+            LyricsSubcommand::Detach {
+                playlist_name,
+                index,
+            } => Self::attach(playlist_name, *index, None),
+        }
+    }
+
+    fn attach(playlist_name: &str, index: i32, file: Option<&PathBuf>) {
+        let mut playlist_info = PlaylistInfo::load_or_exit(playlist_name);
+
+        let index = match get_index(index, playlist_info.songs.len()) {
+            Ok(i) => i,
+            Err(e) => {
+                println!("{}", e);
+                process::exit(1);
+            }
+        };
+
+        let lyrics_path = match file {
+            Some(file) => match Self::check_lyrics(file) {
+                Ok(path) => Some(path),
+                Err(e) => {
+                    println!(
+                        "Failed to read lyrics from {}! Error: {}",
+                        file.display(),
+                        e
+                    );
+                    process::exit(1);
+                }
+            },
+            None => None,
+        };
+
+        let song = &mut playlist_info.songs[index];
+        song.lyrics_path = lyrics_path;
+
+        match &song.lyrics_path {
+            Some(path) => println!(r#"Attached {} to "{}""#, path.display(), song.song_name),
+            None => println!(r#"Removed the lyrics from "{}""#, song.song_name),
+        }
+
+        playlist_info.save_or_exit();
+    }
+
+    /// make sure the file has lyrics in it and return the path to store
+    fn check_lyrics(file: &Path) -> anyhow::Result<PathBuf> {
+        let lyrics = Lyrics::load(file)?;
+
+        if lyrics.lines.is_empty() {
+            return Err(anyhow::anyhow!("No timed lyrics found in the file"));
+        }
+
+        Ok(library::to_stored(file))
+    }
+}
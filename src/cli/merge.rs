@@ -0,0 +1,105 @@
+use std::{collections::HashSet, process};
+
+use clap::Args;
+
+use crate::util::{dedupe::path_key, playlist_names};
+
+use super::data::{PlaylistInfo, Song};
+
+#[derive(Args)]
+pub struct MergePlaylists {
+    /// The playlist whose songs go first
+    first: String,
+
+    /// The playlist whose songs go after the songs of the first one
+    second: String,
+
+    /// The playlist to put the songs into. It can be one of the merged
+    /// playlists, otherwise it must not exist yet
+    #[clap(long)]
+    into: String,
+}
+
+impl MergePlaylists {
+    pub fn handle(&self) {
+        let first = PlaylistInfo::load_or_exit(&self.first);
+        let second = PlaylistInfo::load_or_exit(&self.second);
+
+        let mut merged = if self.into == self.first {
+            first.clone()
+        } else if self.into == self.second {
+            second.clone()
+        } else if playlist_names().unwrap_or_default().contains(&self.into) {
+            println!(
+                r#"Playlist "{}" already exists! Merge into one of the merged playlists or a new one"#,
+                self.into
+            );
+            process::exit(1);
+        } else {
+            PlaylistInfo::new(&self.into)
+        };
+
+        let (songs, duplicates) = merge_songs(&first.songs, &second.songs);
+        merged.songs = songs;
+        merged.save_or_exit();
+
+        println!(
+            r#"Merged "{}" and "{}" into "{}" with {} songs, {} duplicates were left out"#,
+            self.first,
+            self.second,
+            self.into,
+            merged.songs.len(),
+            duplicates
+        );
+    }
+}
+
+/// the songs of both playlists in order, without the songs that play the same
+/// part of a file as a song before them. Returns how many were left out too
+fn merge_songs(first: &[Song], second: &[Song]) -> (Vec<Song>, usize) {
+    let mut seen = HashSet::new();
+    let mut songs = Vec::new();
+    let mut duplicates = 0;
+
+    for song in first.iter().chain(second) {
+        if seen.insert(path_key(song)) {
+            songs.push(song.clone());
+        } else {
+            duplicates += 1;
+        }
+    }
+
+    (songs, duplicates)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::cli::data::Song;
+
+    use super::merge_songs;
+
+    fn song(name: &str, path: &str, start_at: Option<f64>) -> Song {
+        Song {
+            start_at,
+            ..Song::new(name.to_string(), PathBuf::from(path), None, 1.0)
+        }
+    }
+
+    #[test]
+    fn duplicates_are_left_out() {
+        let first = [song("a", "/a.wav", None), song("b", "/b.wav", None)];
+        let second = [
+            song("b again", "/b.wav", None),
+            song("b chapter", "/b.wav", Some(30.0)),
+            song("c", "/c.wav", None),
+        ];
+
+        let (songs, duplicates) = merge_songs(&first, &second);
+        let names = songs.iter().map(|song| song.song_name.as_str());
+
+        assert_eq!(names.collect::<Vec<_>>(), ["a", "b", "b chapter", "c"]);
+        assert_eq!(duplicates, 1);
+    }
+}
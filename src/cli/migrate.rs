@@ -0,0 +1,126 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::Context;
+use clap::Args;
+
+use crate::util::{
+    migration::{self, PLAYLIST_VERSION, SETTINGS_VERSION},
+    playlist_info_path, playlist_names,
+    settings::Settings,
+    storage::{storage, StorageKind},
+};
+
+use super::data::PlaylistInfo;
+
+#[derive(Args)]
+pub struct Migrate {}
+
+impl Migrate {
+    pub fn handle(&self) {
+        // the database is written in the newest layout when it is imported
+        if storage().kind() != StorageKind::Json {
+            println!("Everything in {} is up to date", storage().kind());
+            return;
+        }
+
+        let mut failed = 0;
+
+        if let Err(e) = Self::migrate_settings() {
+            println!("Failed to upgrade the settings! Error: {:#}", e);
+            failed += 1;
+        }
+
+        let names = match playlist_names() {
+            Ok(v) => v,
+            Err(e) => {
+                println!("Failed to read the playlists! Error: {}", e);
+                process::exit(1);
+            }
+        };
+
+        for name in names {
+            match Self::migrate_playlist(&name) {
+                Ok(Some(version)) => println!(
+                    r#"Upgraded "{}" from version {} to {}"#,
+                    name, version, PLAYLIST_VERSION
+                ),
+                Ok(None) => println!(r#""{}" is up to date"#, name),
+                Err(e) => {
+                    println!(r#"Failed to upgrade "{}"! Error: {:#}"#, name, e);
+                    failed += 1;
+                }
+            }
+        }
+
+        if failed > 0 {
+            process::exit(1);
+        }
+    }
+
+    /// upgrade the settings file if it is from an older version
+    fn migrate_settings() -> anyhow::Result<()> {
+        let path = PathBuf::from(Settings::path());
+
+        if !path.is_file() {
+            return Ok(());
+        }
+
+        let version = migration::settings_version(&read_json(&path)?);
+
+        if version == SETTINGS_VERSION {
+            println!("The settings are up to date");
+            return Ok(());
+        }
+
+        backup(&path, version)?;
+        Settings::read_settings()?.save()?;
+
+        println!(
+            "Upgraded the settings from version {} to {}",
+            version, SETTINGS_VERSION
+        );
+
+        Ok(())
+    }
+
+    /// returns the old version if the playlist was upgraded
+    fn migrate_playlist(playlist_name: &str) -> anyhow::Result<Option<u32>> {
+        let path = playlist_info_path(playlist_name);
+        let version = migration::playlist_version(&read_json(&path)?);
+
+        if version == PLAYLIST_VERSION {
+            return Ok(None);
+        }
+
+        // fails for versions that are too new, so nothing is written
+        migration::migrate_playlist(read_json(&path)?)?;
+
+        // loading moves the songs into the song library, so the backup is
+        // made first
+        backup(&path, version)?;
+        PlaylistInfo::load(playlist_name)?.save()?;
+
+        Ok(Some(version))
+    }
+}
+
+fn read_json(path: &Path) -> anyhow::Result<serde_json::Value> {
+    let data = fs::read_to_string(path)?;
+
+    serde_json::from_str(&data).with_context(|| format!("{} is not valid json", path.display()))
+}
+
+/// copy the file to `<file>.v<version>.bak` before it is upgraded
+fn backup(path: &Path, version: u32) -> anyhow::Result<()> {
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(format!(".v{}.bak", version));
+
+    fs::copy(path, &backup_path)
+        .with_context(|| format!("Failed to back up {}", path.display()))?;
+
+    Ok(())
+}
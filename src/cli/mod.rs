@@ -1,17 +1,40 @@
 use self::{
-    add::Add, create::Create, delete::Delete, play::Play, rename::Rename, search::Search,
-    settings::ChangeSettings,
+    add::Add, bundle::BundleCommand, copy::CopyPlaylist, create::Create, dedupe::Dedupe,
+    delete::Delete, diff::DiffPlaylists, export::Export, history::History, import::Import,
+    list::List, lyrics::LyricsCommand, merge::MergePlaylists, migrate::Migrate, play::Play,
+    relink::Relink, rename::Rename, render::Render, restore::Restore, search::Search,
+    settings::ChangeSettings, show::Show, song::SongCommand, sort::Sort, storage::StorageCommand,
+    tag::Tag,
 };
 use clap::Parser;
 
 mod add;
+mod bundle;
+mod copy;
 mod create;
 pub mod data;
+mod dedupe;
 mod delete;
+mod diff;
+mod export;
+mod history;
+mod import;
+mod list;
+mod lyrics;
+mod merge;
+mod migrate;
 mod play;
+mod relink;
 mod rename;
+mod render;
+mod restore;
 mod search;
 mod settings;
+mod show;
+mod song;
+mod sort;
+mod storage;
+mod tag;
 
 #[derive(Parser)]
 #[clap(
@@ -37,6 +60,25 @@ impl Cli {
             Create(create) => create.handle(),
             Rename(rename) => rename.handle(),
             Delete(delete) => delete.handle(),
+            History(history) => history.handle(),
+            Show(show) => show.handle(),
+            Tag(tag) => tag.handle(),
+            Render(render) => render.handle(),
+            Lyrics(lyrics) => lyrics.handle(),
+            Migrate(migrate) => migrate.handle(),
+            Restore(restore) => restore.handle(),
+            Relink(relink) => relink.handle(),
+            Export(export) => export.handle(),
+            Import(import) => import.handle(),
+            Bundle(bundle) => bundle.handle(),
+            Dedupe(dedupe) => dedupe.handle(),
+            Storage(storage) => storage.handle(),
+            List(list) => list.handle(),
+            Song(song) => song.handle(),
+            Copy(copy) => copy.handle(),
+            Merge(merge) => merge.handle(),
+            Diff(diff) => diff.handle(),
+            Sort(sort) => sort.handle(),
         }
     }
 }
@@ -63,4 +105,62 @@ enum Subcommands {
 
     /// Delete a playlist
     Delete(Delete),
+
+    /// Show the songs you have listened to
+    History(History),
+
+    /// Show the songs of a playlist without playing it
+    Show(Show),
+
+    /// Add, remove or list the tags of songs
+    Tag(Tag),
+
+    /// Mix every song of a playlist into a single wav file
+    Render(Render),
+
+    /// Attach synchronized lyrics to songs
+    Lyrics(LyricsCommand),
+
+    /// Upgrade every playlist and the settings to the newest file layout. A
+    /// backup is kept next to every upgraded file
+    Migrate(Migrate),
+
+    /// Roll a playlist back to one of its backups
+    Restore(Restore),
+
+    /// Fix the paths of songs whose files were moved
+    Relink(Relink),
+
+    /// Write a playlist into a file other players can read
+    Export(Export),
+
+    /// Create a playlist from a playlist file of another player
+    Import(Import),
+
+    /// Share a playlist together with its audio files
+    Bundle(BundleCommand),
+
+    /// Find songs that are in a playlist more than once and remove them
+    Dedupe(Dedupe),
+
+    /// Move the playlists between the json files and a sqlite database
+    Storage(StorageCommand),
+
+    /// Show every playlist with how many songs it has, how long and how big it is
+    List(List),
+
+    /// Remove, move, rename or change a song without opening the player
+    Song(SongCommand),
+
+    /// Copy a playlist into a new one
+    Copy(CopyPlaylist),
+
+    /// Put the songs of two playlists together, leaving out duplicates
+    Merge(MergePlaylists),
+
+    /// Show the songs that are only in one of two playlists
+    Diff(DiffPlaylists),
+
+    /// Change the order of the songs in a playlist
+    Sort(Sort),
 }
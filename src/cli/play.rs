@@ -1,51 +1,132 @@
 use std::{
     cell::Cell,
+    io::{self, Write},
     process,
     str::FromStr,
     sync::{
         atomic::{AtomicUsize, Ordering},
         mpsc, Arc,
     },
-    thread,
+    thread::{self, JoinHandle},
     time::Duration,
 };
 
-use anyhow::Context;
-use basic_quick_lib::{cli_util::pause, io_util::input_trim};
+use basic_quick_lib::{cli_util::pause, io_util::input_trim, time::LocalTime};
+use chrono::Local;
 use parking_lot::{Mutex, RwLock};
-use soloud::{AudioExt, Handle, LoadExt, Soloud, Wav};
+use soloud::{AudioExt, LoadExt, Wav};
 use termcolor::ColorSpec;
 
 use crate::{
     cli::data::Song,
     util::{
-        colored, get_index, help_print, multiplied_volume,
+        audio::{AudioBackend, AudioOutput},
+        colored, get_index, help_print,
+        history::{self, PlayEvent},
+        lyrics::Lyrics,
+        multiplied_volume, playlist_names,
         settings::{PlaybackMode, SETTINGS},
         shuffle_vec, to_index,
+        visualizer::{self, VisualizerMode},
     },
 };
 
-use super::data::PlaylistInfo;
+use super::data::{find_song, PlaylistInfo};
 
 #[derive(clap::Args)]
 pub struct Play {
-    playlist_name: String,
+    /// The playlist to play. Can be left out if --tag is given, which plays the
+    /// tagged songs from every playlist
+    #[clap(required_unless_present = "tag")]
+    playlist_name: Option<String>,
+
+    /// Only play the songs that are marked as favorite
+    #[clap(short, long, action)]
+    favorites: bool,
+
+    /// Only play the songs that are rated at least this (1 to 5)
+    #[clap(short, long, value_parser = clap::value_parser!(u8).range(1..=5))]
+    min_rating: Option<u8>,
+
+    /// Only play the songs with this tag
+    #[clap(short, long)]
+    tag: Option<String>,
+
+    /// Where to play the music. Can be either soloud (the sound card) or null
+    /// (no sound at all). Uses the one in the settings if not given
+    #[clap(long)]
+    audio_backend: Option<AudioBackend>,
 }
 
 impl Play {
     pub fn handle(&self) {
-        let playlist_info = match PlaylistInfo::load(&self.playlist_name) {
-            Ok(v) => v,
-            Err(err) => {
-                println!(
-                    r#"Failed to load playlist "{}"! Error: {}"#,
-                    self.playlist_name, err
-                );
-                return;
+        let playlist_info = match &self.playlist_name {
+            Some(playlist_name) => {
+                let playlist_info = PlaylistInfo::load_or_exit(playlist_name);
+
+                if self.has_filters() {
+                    playlist_info.filtered(|song| self.matches(song))
+                } else {
+                    playlist_info
+                }
             }
+            None => match self.every_playlist() {
+                Ok(v) => v,
+                Err(err) => {
+                    println!("Failed to load the playlists! Error: {}", err);
+                    return;
+                }
+            },
         };
 
-        PlayMenu::new(playlist_info).start();
+        if playlist_info.songs.is_empty() && playlist_info.sources.is_some() {
+            println!("No song matches the filters!");
+            return;
+        }
+
+        let backend = self
+            .audio_backend
+            .unwrap_or_else(|| SETTINGS.read().audio_backend);
+
+        PlayMenu::new(playlist_info, backend).start();
+    }
+
+    /// a view of the songs in every playlist that matches the filters
+    fn every_playlist(&self) -> anyhow::Result<PlaylistInfo> {
+        let playlists = playlist_names()?
+            .iter()
+            .filter_map(|name| PlaylistInfo::load(name).ok())
+            .collect::<Vec<_>>();
+
+        let name = format!("#{}", self.tag.as_deref().unwrap_or_default());
+
+        Ok(PlaylistInfo::view_of(&name, &playlists, |song| {
+            self.matches(song)
+        }))
+    }
+
+    fn has_filters(&self) -> bool {
+        self.favorites || self.min_rating.is_some() || self.tag.is_some()
+    }
+
+    fn matches(&self, song: &Song) -> bool {
+        if self.favorites && !song.favorite {
+            return false;
+        }
+
+        if let Some(min_rating) = self.min_rating {
+            if song.rating.unwrap_or(0) < min_rating {
+                return false;
+            }
+        }
+
+        if let Some(tag) = &self.tag {
+            if !song.has_tag(tag) {
+                return false;
+            }
+        }
+
+        true
     }
 }
 
@@ -59,8 +140,31 @@ enum Message {
     SetMultiplier(f32),
     PlayPrevious,
     PlayNext,
-    UpdateName { index: usize, new_name: String },
+    UpdateName {
+        index: usize,
+        new_name: String,
+    },
     Delete(usize),
+    /// move the song at `from` so it ends up at `to`
+    Move {
+        from: usize,
+        to: usize,
+    },
+    SetRating(u8),
+    ToggleFavorite,
+    /// set the visualizer mode, or switch to the next one if it is `None`
+    SetVisualizer(Option<VisualizerMode>),
+    /// trim the current song at the position it is playing at
+    Trim(TrimPoint),
+    Exit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrimPoint {
+    /// the song starts here from the next time it is played
+    Start,
+    /// the song ends here, which also ends it right now
+    End,
 }
 
 struct PlayMenu {
@@ -68,6 +172,8 @@ struct PlayMenu {
     commands_receiver: Arc<Mutex<mpsc::Receiver<Message>>>,
     playlist_info: Arc<RwLock<PlaylistInfo>>,
     currently_playing_index: Arc<AtomicUsize>,
+    play_thread: Option<JoinHandle<()>>,
+    backend: AudioBackend,
 }
 
 /// A struct that represents all the info necessary for song playing
@@ -76,10 +182,20 @@ struct PlayingInfo {
     is_paused: Cell<bool>,
     randomized_indices: Vec<usize>,
     current_duration: Duration,
+    song_started: LocalTime,
+    lyrics: Option<Lyrics>,
+    /// where the song starts in its audio file
+    start_at: Duration,
+    /// where the song ends in its audio file, or the end of the file if `None`
+    end_at: Option<Duration>,
+    /// why saving or reloading the playlist, or the last command, failed. It
+    /// is shown below the playlist until the playlist is saved, since printing
+    /// it would be cleared by the next redraw
+    error: Option<String>,
 }
 
 impl PlayMenu {
-    fn new(playlist_info: PlaylistInfo) -> Self {
+    fn new(playlist_info: PlaylistInfo, backend: AudioBackend) -> Self {
         let channel = mpsc::channel::<Message>();
 
         Self {
@@ -87,6 +203,8 @@ impl PlayMenu {
             commands_receiver: Arc::new(Mutex::new(channel.1)),
             playlist_info: Arc::new(RwLock::new(playlist_info)),
             currently_playing_index: Arc::new(AtomicUsize::new(0)),
+            play_thread: None,
+            backend,
         }
     }
 
@@ -100,13 +218,10 @@ impl PlayMenu {
         wav: &mut Wav,
         playing_info: &PlayingInfo,
     ) -> Result<(), (String, String)> {
-        let Song {
-            song_name,
-            path_to_song,
-            ..
-        } = &playlist_info.songs[playing_info.currently_playing.load(Ordering::SeqCst)];
+        let song = &playlist_info.songs[playing_info.currently_playing.load(Ordering::SeqCst)];
+        let (song_name, path_to_song) = (&song.song_name, song.path());
 
-        wav.load(path_to_song)
+        wav.load(&path_to_song)
             .map_err(|e| (
                 format!("Failed to load song \"{}\"! Error: {}", song_name, e), 
                 format!(
@@ -116,7 +231,9 @@ impl PlayMenu {
             ))
     }
 
-    fn print_info(playlist_info: &PlaylistInfo, playing_info: &PlayingInfo) {
+    /// `position` is where the output is in the audio file, the lyrics are
+    /// shown for it
+    fn print_info(playlist_info: &PlaylistInfo, playing_info: &PlayingInfo, position: Duration) {
         // just ignore it if failed to clear
         let _ = clearscreen::clear();
 
@@ -135,7 +252,14 @@ impl PlayMenu {
         for (index, song) in playlist_info.songs.iter().enumerate() {
             let is_current = index == playing_info.currently_playing.load(Ordering::SeqCst);
 
-            let text = format!("{}. {}", index + 1, song.song_name);
+            let mut text = format!("{}. {}", index + 1, song.song_name);
+
+            if song.favorite {
+                text.push_str(" ★");
+            }
+            if let Some(rating) = song.rating {
+                text.push_str(&format!(" ({}/5)", rating));
+            }
 
             if is_current {
                 print!("-> ");
@@ -145,33 +269,149 @@ impl PlayMenu {
             }
         }
 
+        if let Some(lyrics) = &playing_info.lyrics {
+            let [previous, current, next] = lyrics.around(position);
+
+            println!();
+            println!("   {}", previous.unwrap_or_default());
+            colored::writeln(
+                ColorSpec::new().set_bold(true),
+                &format!(">> {}", current.unwrap_or_default()),
+            );
+            println!("   {}", next.unwrap_or_default());
+        }
+
         if playing_info.is_paused.get() {
             println!();
             println!("Paused");
         }
+
+        if let Some(error) = &playing_info.error {
+            println!();
+            println!("{}", error);
+        }
+    }
+
+    fn visualizer_rows(output: &dyn AudioOutput) -> Option<Vec<String>> {
+        use visualizer::{HEIGHT, WIDTH};
+
+        match SETTINGS.read().visualizer {
+            VisualizerMode::Off => None,
+            VisualizerMode::Bars => {
+                Some(visualizer::render_bars(&output.calc_fft(), WIDTH, HEIGHT))
+            }
+            VisualizerMode::Wave => Some(visualizer::render_wave(&output.wave(), WIDTH, HEIGHT)),
+        }
+    }
+
+    fn print_visualizer(output: &dyn AudioOutput) {
+        let rows = match Self::visualizer_rows(output) {
+            Some(v) => v,
+            None => return,
+        };
+
+        println!();
+        for row in rows {
+            println!("{}", row);
+        }
+    }
+
+    /// draw the visualizer over the one printed by the last redraw, which is
+    /// right above the prompt. The screen is not cleared and the cursor is
+    /// put back where it was, so whatever is being typed stays
+    fn redraw_visualizer(output: &dyn AudioOutput) {
+        let rows = match Self::visualizer_rows(output) {
+            Some(v) => v,
+            None => return,
+        };
+
+        Self::draw_rows(&rows, rows.len());
+    }
+
+    /// draw the lyrics over the ones printed by the last redraw, the same way
+    /// as the visualizer, so moving to the next line does not wipe the prompt
+    fn redraw_lyrics(output: &dyn AudioOutput, playing_info: &PlayingInfo) {
+        let lyrics = match &playing_info.lyrics {
+            Some(v) => v,
+            None => return,
+        };
+
+        let [previous, current, next] = lyrics.around(output.position());
+        let rows = [
+            format!("   {}", previous.unwrap_or_default()),
+            format!("\x1b[1m>> {}\x1b[0m", current.unwrap_or_default()),
+            format!("   {}", next.unwrap_or_default()),
+        ];
+
+        // the rows print_info and print_visualizer printed below the lyrics
+        let mut below = 0;
+
+        if playing_info.is_paused.get() {
+            below += 2;
+        }
+        if let Some(error) = &playing_info.error {
+            below += 1 + error.lines().count();
+        }
+        if let Some(visualizer) = Self::visualizer_rows(output) {
+            below += 1 + visualizer.len();
+        }
+
+        Self::draw_rows(&rows, below + rows.len());
+    }
+
+    /// draw the rows starting `up` lines above the prompt. The screen is not
+    /// cleared and the cursor is put back where it was
+    fn draw_rows(rows: &[String], up: usize) {
+        // save the cursor and go to the start of the first row
+        let mut text = format!("\x1b7\x1b[{}F", up);
+
+        for (index, row) in rows.iter().enumerate() {
+            if index > 0 {
+                text.push_str("\x1b[1E");
+            }
+
+            text.push_str("\x1b[2K");
+            text.push_str(row);
+        }
+
+        text.push_str("\x1b8");
+
+        let mut stdout = io::stdout().lock();
+        // just ignore it if failed to draw
+        let _ = stdout
+            .write_all(text.as_bytes())
+            .and_then(|_| stdout.flush());
+    }
+
+    fn redraw(output: &dyn AudioOutput, playlist_info: &PlaylistInfo, playing_info: &PlayingInfo) {
+        Self::print_info(playlist_info, playing_info, output.position());
+        Self::print_visualizer(output);
     }
 
     fn update_volume(
-        sl: &mut Soloud,
-        handle: Handle,
+        output: &mut dyn AudioOutput,
         playlist_info: &PlaylistInfo,
         playing_info: &PlayingInfo,
     ) {
-        sl.set_volume(
-            handle,
-            multiplied_volume(
-                SETTINGS.read().volume,
-                playlist_info.songs[playing_info.currently_playing.load(Ordering::SeqCst)]
-                    .sound_multiplier,
-            ),
-        );
+        output.set_volume(multiplied_volume(
+            SETTINGS.read().volume,
+            playlist_info.songs[playing_info.currently_playing.load(Ordering::SeqCst)]
+                .sound_multiplier,
+        ));
+    }
+
+    /// save the playlist, keeping the error to show it if it failed
+    fn save(playlist_info: &mut PlaylistInfo, error: &mut Option<String>) {
+        *error = playlist_info
+            .save()
+            .err()
+            .map(|e| playlist_info.failed("save", &e));
     }
 
     fn handle_msg(
         message: Message,
-        sl: &mut Soloud,
+        output: &mut dyn AudioOutput,
         // is_paused: &Cell<bool>,
-        handle: Handle,
         // currently_playing: &AtomicUsize,
         playlist_info: &mut PlaylistInfo,
         // randomized_indices: &mut Vec<usize>,
@@ -188,15 +428,15 @@ impl PlayMenu {
         match message {
             Pause => {
                 is_paused.set(true);
-                sl.set_pause(handle, is_paused.get());
+                output.set_pause(is_paused.get());
             }
             Resume => {
                 is_paused.set(false);
-                sl.set_pause(handle, is_paused.get());
+                output.set_pause(is_paused.get());
             }
             PauseOrResume => {
                 is_paused.set(!is_paused.get());
-                sl.set_pause(handle, is_paused.get());
+                output.set_pause(is_paused.get());
             }
             // do nothing because it will reprint anyways lol it
             // feels stupid
@@ -214,16 +454,16 @@ impl PlayMenu {
                     setting.save().unwrap_or_default();
                 }
 
-                Self::update_volume(sl, handle, playlist_info, playing_info);
+                Self::update_volume(output, playlist_info, playing_info);
             }
             SetMultiplier(new_mul) => {
                 {
                     playlist_info.songs[currently_playing.load(Ordering::SeqCst)]
                         .sound_multiplier = new_mul;
-                    playlist_info.save();
+                    Self::save(playlist_info, &mut playing_info.error);
                 };
 
-                Self::update_volume(sl, handle, playlist_info, playing_info);
+                Self::update_volume(output, playlist_info, playing_info);
             }
             PlayPrevious => {
                 if currently_playing.load(Ordering::SeqCst) == 0 {
@@ -243,68 +483,234 @@ impl PlayMenu {
             }
             UpdateName { index, new_name } => {
                 playlist_info.songs[index].song_name = new_name;
-                playlist_info.save();
+                Self::save(playlist_info, &mut playing_info.error);
             }
             Delete(index) => {
-                playlist_info.songs.remove(index);
-                playlist_info.save();
+                if let Err(e) = playlist_info.remove_song(index) {
+                    playing_info.error = Some(playlist_info.failed("save", &e));
+                }
                 shuffle_vec(
                     &mut playing_info.randomized_indices,
                     playlist_info.songs.len(),
                 );
             }
+            Move { from, to } => {
+                // the input thread checked the indices against the songs it
+                // saw, which might have been deleted since
+                let len = playlist_info.songs.len();
+                if from >= len || to >= len {
+                    playing_info.error = Some(format!(
+                        "Failed to move the song! There are only {} songs now",
+                        len
+                    ));
+                    return SongInstruction::None;
+                }
+
+                playlist_info.move_song(from, to);
+
+                // keep playing the same song, and keep the songs that are
+                // still to be played in random mode
+                let current = currently_playing.load(Ordering::SeqCst);
+                currently_playing.store(moved_index(current, from, to), Ordering::SeqCst);
+
+                for index in playing_info.randomized_indices.iter_mut() {
+                    *index = moved_index(*index, from, to);
+                }
+
+                Self::save(playlist_info, &mut playing_info.error);
+            }
+            SetRating(rating) => {
+                playlist_info.songs[currently_playing.load(Ordering::SeqCst)].rating = Some(rating);
+                Self::save(playlist_info, &mut playing_info.error);
+            }
+            ToggleFavorite => {
+                let song = &mut playlist_info.songs[currently_playing.load(Ordering::SeqCst)];
+                song.favorite = !song.favorite;
+                Self::save(playlist_info, &mut playing_info.error);
+            }
+            SetVisualizer(mode) => {
+                let mut setting = SETTINGS.write();
+                setting.visualizer = mode.unwrap_or_else(|| setting.visualizer.next());
+                setting.save().unwrap_or_default();
+            }
+            Trim(point) => {
+                let position = output.position();
+                // keep the file tidy, milliseconds are precise enough
+                let seconds = (position.as_secs_f64() * 1000.0).round() / 1000.0;
+                let song = &mut playlist_info.songs[currently_playing.load(Ordering::SeqCst)];
+
+                // a song that ends as soon as it starts would be skipped over
+                // and over again
+                let (start, end) = match point {
+                    TrimPoint::Start => (seconds, song.end_at),
+                    TrimPoint::End => (song.start_at.unwrap_or_default(), Some(seconds)),
+                };
+                if end.is_some_and(|end| end <= start) {
+                    playing_info.error = Some(format!(
+                        "Failed to trim the song! It would end at {}s, before it starts at {}s",
+                        end.unwrap_or_default(),
+                        start
+                    ));
+                    return SongInstruction::None;
+                }
+
+                match point {
+                    TrimPoint::Start => song.start_at = Some(seconds),
+                    TrimPoint::End => {
+                        song.end_at = Some(seconds);
+                        playing_info.end_at = Some(position);
+                    }
+                }
+
+                Self::save(playlist_info, &mut playing_info.error);
+            }
+            Exit => return SongInstruction::Exit,
         }
 
         SongInstruction::None
     }
 
     fn recv_cmd(
-        sl: &mut Soloud,
-        handle: Handle,
+        output: &mut dyn AudioOutput,
         receiver: &mpsc::Receiver<Message>,
         playlist_info: &RwLock<PlaylistInfo>,
         playing_info: &mut PlayingInfo,
     ) -> SongInstruction {
         const SLEEP_DURATION: Duration = Duration::from_millis(10);
+        const VISUALIZER_INTERVAL: Duration = Duration::from_millis(200);
+        const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+        let mut since_redraw = Duration::ZERO;
+        let mut since_reload = Duration::ZERO;
+        let mut lyric_index = None;
 
-        while sl.voice_count() > 0 {
+        while output.is_playing() {
             // pause the loop a little so it won't take too much cpu
             // power
             thread::sleep(SLEEP_DURATION);
 
+            // the song ends before its audio file does
+            if playing_info
+                .end_at
+                .is_some_and(|end_at| output.position() >= end_at)
+            {
+                output.stop();
+                break;
+            }
+
             if !playing_info.is_paused.get() {
                 playing_info.current_duration += SLEEP_DURATION;
+                since_redraw += SLEEP_DURATION;
+            }
+
+            // pick up the songs other processes added to the playlist
+            since_reload += SLEEP_DURATION;
+            if since_reload >= RELOAD_INTERVAL {
+                since_reload = Duration::ZERO;
+
+                if Self::reload_playlist(&mut playlist_info.write(), playing_info) {
+                    if playlist_info.read().songs.is_empty() {
+                        return SongInstruction::Exit;
+                    }
+
+                    Self::redraw(output, &playlist_info.read(), playing_info);
+                }
+            }
+
+            // the visualizer is redrawn by the playing thread, so the input
+            // thread can keep waiting for commands
+            if since_redraw >= VISUALIZER_INTERVAL {
+                since_redraw = Duration::ZERO;
+
+                Self::redraw_visualizer(output);
+            }
+
+            // move to the next line of the lyrics when it is time to
+            if let Some(lyrics) = &playing_info.lyrics {
+                let index = lyrics.current_index(output.position());
+
+                if index != lyric_index {
+                    lyric_index = index;
+                    Self::redraw_lyrics(output, playing_info);
+                }
             }
 
             // try to recv to see if there is any command, or else
             // continue playing the song
             if let Ok(message) = receiver.try_recv() {
-                let instruction = Self::handle_msg(
-                    message,
-                    sl,
-                    handle,
-                    &mut playlist_info.write(),
-                    playing_info,
-                );
+                let instruction =
+                    Self::handle_msg(message, output, &mut playlist_info.write(), playing_info);
 
                 if instruction != SongInstruction::None {
                     return instruction;
                 }
 
-                Self::print_info(&playlist_info.read(), playing_info);
+                Self::redraw(output, &playlist_info.read(), playing_info);
             }
         }
 
         SongInstruction::None
     }
 
+    /// merge in the changes other processes saved. Returns true if anything
+    /// changed
+    fn reload_playlist(playlist_info: &mut PlaylistInfo, playing_info: &mut PlayingInfo) -> bool {
+        let index = playing_info.currently_playing.load(Ordering::SeqCst);
+        let current = playlist_info
+            .songs
+            .get(index)
+            .map(|song| (song.path_to_song.clone(), song.start_at));
+        let len = playlist_info.songs.len();
+
+        match playlist_info.reload() {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(e) => {
+                let error = Some(playlist_info.failed("reload", &e));
+                // only redraw the first time, it is tried again every second
+                let changed = playing_info.error != error;
+                playing_info.error = error;
+                return changed;
+            }
+        }
+
+        if playlist_info.songs.is_empty() {
+            return true;
+        }
+
+        // keep pointing at the same song even if the songs before it changed
+        let new_index = current
+            .and_then(|(path, start_at)| {
+                playlist_info
+                    .songs
+                    .iter()
+                    .position(|song| song.path_to_song == path && song.start_at == start_at)
+            })
+            .unwrap_or_else(|| index.min(playlist_info.songs.len() - 1));
+
+        playing_info
+            .currently_playing
+            .store(new_index, Ordering::SeqCst);
+
+        if len != playlist_info.songs.len() {
+            shuffle_vec(
+                &mut playing_info.randomized_indices,
+                playlist_info.songs.len(),
+            );
+        }
+
+        true
+    }
+
+    /// move on to the next song. Returns false if there is none, which only
+    /// happens at the end of the playlist in sequel mode
     fn next_song(
+        playback_mode: PlaybackMode,
         playlist_info: &PlaylistInfo,
         // currently_playing: &AtomicUsize,
         // randomized_indices: &mut Vec<usize>,
         playing_info: &mut PlayingInfo,
-    ) {
-        let setting = SETTINGS.read();
+    ) -> bool {
         let len = playlist_info.songs.len();
 
         let PlayingInfo {
@@ -315,11 +721,11 @@ impl PlayMenu {
 
         // after the song been played, change the current playing song
         // based on the playback mode choice
-        match setting.playback_mode {
+        match playback_mode {
             PlaybackMode::Sequel => {
                 currently_playing.fetch_add(1, Ordering::SeqCst);
                 if currently_playing.load(Ordering::SeqCst) >= len {
-                    process::exit(0);
+                    return false;
                 }
             }
             PlaybackMode::LoopOnce => {}
@@ -344,14 +750,49 @@ impl PlayMenu {
                 );
             }
         }
+
+        true
+    }
+
+    fn record_history(playlist_name: &str, song: &Song, playing_info: &PlayingInfo, skipped: bool) {
+        let event = PlayEvent {
+            song_name: song.song_name.clone(),
+            author: song.author.clone(),
+            path_to_song: song.path(),
+            playlist: playlist_name.to_string(),
+            started: playing_info.song_started.clone(),
+            seconds_listened: playing_info.current_duration.as_secs(),
+            skipped,
+        };
+
+        // failing to write the history should never stop the music
+        let _ = history::append(&event);
     }
 
-    fn handle_play(&self) {
+    fn record_stats(
+        playlist_info: &mut PlaylistInfo,
+        song_index: usize,
+        song: &Song,
+        playing_info: &mut PlayingInfo,
+        skipped: bool,
+    ) {
+        // the song might have been moved or deleted while it was playing
+        let stored_song = match find_song(&playlist_info.songs, song, song_index) {
+            Some(index) => &mut playlist_info.songs[index],
+            None => return,
+        };
+
+        stored_song.record_play(playing_info.song_started.clone(), skipped);
+        Self::save(playlist_info, &mut playing_info.error);
+    }
+
+    fn handle_play(&mut self) {
         let playlist_info = Arc::clone(&self.playlist_info);
         let receiver = Arc::clone(&self.commands_receiver);
         let currently_playing = Arc::clone(&self.currently_playing_index);
+        let backend = self.backend;
 
-        thread::spawn(move || {
+        self.play_thread = Some(thread::spawn(move || {
             let songs_len = playlist_info.read().songs.len();
 
             if songs_len == 0 {
@@ -360,9 +801,14 @@ impl PlayMenu {
                 process::exit(1);
             }
 
-            let mut sl = Soloud::default()
-                .with_context(|| "Failed to get player!")
-                .unwrap();
+            let mut output = match backend.create_output() {
+                Ok(v) => v,
+                Err(e) => {
+                    println!("{:#}", e);
+                    pause();
+                    process::exit(1);
+                }
+            };
 
             let mut wav = Wav::default();
 
@@ -387,45 +833,92 @@ impl PlayMenu {
                 is_paused,
                 randomized_indices,
                 current_duration,
+                song_started: LocalTime(Local::now()),
+                lyrics: None,
+                start_at: Duration::ZERO,
+                end_at: None,
+                error: None,
             };
 
+            // songs split from the chapters of a video share the same file, so
+            // it is only loaded again when the file changes
+            let mut loaded_path = None;
+
             'song_loop: loop {
-                Self::print_info(&playlist_info.read(), &playing_info);
+                let song_index = playing_info.currently_playing.load(Ordering::SeqCst);
+                let song = playlist_info.read().songs[song_index].clone();
+                playing_info.lyrics = Lyrics::for_song(&song);
+                playing_info.current_duration = Duration::ZERO;
+                playing_info.start_at = song.start_offset();
+                playing_info.end_at = song.end_offset();
 
-                if let Err(e) = Self::init_song(&playlist_info.read(), &mut wav, &playing_info) {
-                    println!("{}", e.0);
-                    println!("{}", e.1);
-                    pause();
-                    playing_info.currently_playing.store(0, Ordering::SeqCst);
-                    continue;
+                // the song has not started yet
+                Self::print_info(&playlist_info.read(), &playing_info, playing_info.start_at);
+
+                output.stop();
+
+                if loaded_path.as_ref() != Some(&song.path_to_song) {
+                    loaded_path = None;
+
+                    if let Err(e) = Self::init_song(&playlist_info.read(), &mut wav, &playing_info)
+                    {
+                        println!("{}", e.0);
+                        println!("{}", e.1);
+                        pause();
+                        playing_info.currently_playing.store(0, Ordering::SeqCst);
+                        continue;
+                    }
+
+                    loaded_path = Some(song.path_to_song.clone());
                 }
 
-                let handle = sl.play(&wav);
+                playing_info.song_started = LocalTime(Local::now());
+
+                output.play(&wav);
+
+                if playing_info.start_at > Duration::ZERO {
+                    output.seek(playing_info.start_at);
+                }
 
                 Self::update_volume(
-                    &mut sl,
-                    handle,
+                    output.as_mut(),
                     &playlist_info.read(),
                     // currently_playing.load(Ordering::SeqCst),
                     &playing_info,
                 );
 
                 let instruction = Self::recv_cmd(
-                    &mut sl,
-                    handle,
+                    output.as_mut(),
                     &receiver.lock(),
                     &playlist_info,
                     &mut playing_info,
                 );
 
+                // leaving the player does not skip the song, only moving to
+                // another song does
+                let skipped = instruction == SongInstruction::SkipLoop;
+                Self::record_history(&playlist_info.read().name, &song, &playing_info, skipped);
+                Self::record_stats(
+                    &mut playlist_info.write(),
+                    song_index,
+                    &song,
+                    &mut playing_info,
+                    skipped,
+                );
+
                 match instruction {
                     SongInstruction::None => {}
                     SongInstruction::SkipLoop => continue 'song_loop,
+                    SongInstruction::Exit => return,
                 }
 
-                Self::next_song(&playlist_info.read(), &mut playing_info)
+                let playback_mode = SETTINGS.read().playback_mode;
+
+                if !Self::next_song(playback_mode, &playlist_info.read(), &mut playing_info) {
+                    process::exit(0);
+                }
             }
-        });
+        }));
     }
 
     fn help_menu() {
@@ -460,15 +953,28 @@ impl PlayMenu {
             "Edit the song's name to the one you specified",
         );
         help_print("del <INDEX>", "Delete the song at index");
+        help_print(
+            "move <FROM> <TO>",
+            "Move the song at index FROM so it ends up at index TO",
+        );
+        help_print("rate <1-5>", "Rate the current song");
+        help_print("fav", "Mark or unmark the current song as favorite");
+        help_print(
+            "vis [MODE]",
+            "Set the visualizer. Value can be: off, bars, wave. Switch to the next one if not given",
+        );
+        help_print(
+            "trim <start|end>",
+            "Make the current song start or end at the position it is playing at",
+        );
         println!("Type the index of the song to jump to the song. Example: `4` will jump to the fourth one");
         println!("    - Note that you can pass a negative value to start from the back. Example `-1` will go to the last song");
     }
 
-    fn handle_input(&self) {
+    fn handle_input(&mut self) {
         use Message::*;
 
         let songs = Arc::clone(&self.playlist_info);
-        let song_len = songs.read().songs.len();
         let current_playing_index = Arc::clone(&self.currently_playing_index);
 
         let try_send = |message: Message| {
@@ -490,6 +996,8 @@ impl PlayMenu {
 
             let command = splitted[0];
             let args = &splitted[1..];
+            // songs are deleted and reloaded while playing
+            let song_len = songs.read().songs.len();
 
             match command {
                 "setv" => {
@@ -639,6 +1147,63 @@ impl PlayMenu {
 
                     try_send(Delete(index));
                 }
+                "move" => {
+                    let indices = to_index(args, 0, song_len)
+                        .and_then(|from| Ok((from, to_index(args, 1, song_len)?)));
+
+                    match indices {
+                        Ok((from, to)) => try_send(Move { from, to }),
+                        Err(e) => {
+                            println!("{}. Usage: move <FROM> <TO>", e);
+                            pause();
+                            try_send(Reprint);
+                        }
+                    }
+                }
+                "rate" => {
+                    let rating = match args.first().map(|arg| arg.trim().parse::<u8>()) {
+                        Some(Ok(rating @ 1..=5)) => rating,
+                        _ => {
+                            println!(
+                                "Rating must be a number in between 1 and 5. Usage: rate <1-5>"
+                            );
+                            pause();
+                            try_send(Reprint);
+                            continue;
+                        }
+                    };
+
+                    try_send(SetRating(rating));
+                }
+                "fav" => try_send(ToggleFavorite),
+                "vis" => {
+                    let mode = match args.first().map(|arg| VisualizerMode::from_str(arg)) {
+                        Some(Ok(mode)) => Some(mode),
+                        Some(Err(e)) => {
+                            println!("{}", e);
+                            pause();
+                            try_send(Reprint);
+                            continue;
+                        }
+                        None => None,
+                    };
+
+                    try_send(SetVisualizer(mode));
+                }
+                "trim" => {
+                    let point = match args.first().map(|arg| arg.trim().to_lowercase()) {
+                        Some(arg) if arg == "start" => TrimPoint::Start,
+                        Some(arg) if arg == "end" => TrimPoint::End,
+                        _ => {
+                            println!("Usage: trim <start|end>");
+                            pause();
+                            try_send(Reprint);
+                            continue;
+                        }
+                    };
+
+                    try_send(Trim(point));
+                }
                 num if num.parse::<i32>().is_ok() => {
                     // a really dumb thing to do and hopefully
                     // if let guard can be stabilized in the future
@@ -656,7 +1221,10 @@ impl PlayMenu {
 
                     try_send(IndexJump(index));
                 }
-                "exit" => return,
+                "exit" => {
+                    try_send(Exit);
+                    break;
+                }
                 _ => {
                     println!(
                         "Unknown Command `{}`! Type in `help` for more information!",
@@ -666,6 +1234,12 @@ impl PlayMenu {
                 }
             }
         }
+
+        // wait for the playing thread so that the song that is currently
+        // playing gets recorded into the history
+        if let Some(play_thread) = self.play_thread.take() {
+            let _ = play_thread.join();
+        }
     }
 }
 
@@ -673,23 +1247,428 @@ impl PlayMenu {
 enum SongInstruction {
     None,
     SkipLoop,
+    Exit,
+}
+
+/// where the song at `index` is after the song at `from` was moved to `to`
+fn moved_index(index: usize, from: usize, to: usize) -> usize {
+    if index == from {
+        to
+    } else if from < index && index <= to {
+        index - 1
+    } else if to <= index && index < from {
+        index + 1
+    } else {
+        index
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use soloud::{AudioExt, LoadExt, Soloud, Wav};
+    use std::{
+        cell::Cell,
+        collections::HashSet,
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc, Arc,
+        },
+        time::Duration,
+    };
+
+    use basic_quick_lib::time::LocalTime;
+    use chrono::Local;
+    use parking_lot::RwLock;
+
+    use super::{moved_index, Message, PlayMenu, PlayingInfo, SongInstruction, TrimPoint};
+    use crate::{
+        cli::data::{PlaylistInfo, Song},
+        util::{
+            audio::{test::silent_wav, AudioOutput, NullOutput},
+            multiplied_volume,
+            settings::{PlaybackMode, SETTINGS},
+        },
+    };
+
+    /// every test has a playlist of its own, with its own files, so tests
+    /// that save do not see the songs of each other
+    fn playlist(playlist_name: &str, len: usize) -> PlaylistInfo {
+        let mut playlist_info = PlaylistInfo::new(playlist_name);
+
+        for i in 0..len {
+            let name = format!("song{}", i);
+            let path = PathBuf::from(playlist_name).join(&name);
+            playlist_info.songs.push(Song::new(name, path, None, 1.0));
+        }
+
+        playlist_info
+    }
+
+    fn playing_info(len: usize) -> PlayingInfo {
+        PlayingInfo {
+            currently_playing: Arc::new(AtomicUsize::new(0)),
+            is_paused: Cell::new(false),
+            randomized_indices: (0..len).collect(),
+            current_duration: Duration::ZERO,
+            start_at: Duration::ZERO,
+            end_at: None,
+            song_started: LocalTime(Local::now()),
+            lyrics: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn voice_count() {
+        let mut output = NullOutput::default();
+        let wav = silent_wav(1.0);
+
+        output.play(&wav);
+        assert!(output.is_playing());
+
+        output.finish();
+        assert!(!output.is_playing());
+    }
+
+    #[test]
+    fn next_and_previous_wrap_around() {
+        let mut output = NullOutput::default();
+        let mut playlist_info = playlist("next_and_previous_wrap_around", 3);
+        let mut playing_info = playing_info(3);
+
+        let instruction = PlayMenu::handle_msg(
+            Message::PlayPrevious,
+            &mut output,
+            &mut playlist_info,
+            &mut playing_info,
+        );
+        assert_eq!(instruction, SongInstruction::SkipLoop);
+        assert_eq!(playing_info.currently_playing.load(Ordering::SeqCst), 2);
+
+        PlayMenu::handle_msg(
+            Message::PlayNext,
+            &mut output,
+            &mut playlist_info,
+            &mut playing_info,
+        );
+        assert_eq!(playing_info.currently_playing.load(Ordering::SeqCst), 0);
+
+        PlayMenu::handle_msg(
+            Message::IndexJump(1),
+            &mut output,
+            &mut playlist_info,
+            &mut playing_info,
+        );
+        assert_eq!(playing_info.currently_playing.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn move_keeps_current_song() {
+        let mut output = NullOutput::default();
+        let mut playlist_info = playlist("move_keeps_current_song", 4);
+        let mut playing_info = playing_info(4);
+        playing_info.currently_playing.store(1, Ordering::SeqCst);
+
+        PlayMenu::handle_msg(
+            Message::Move { from: 0, to: 2 },
+            &mut output,
+            &mut playlist_info,
+            &mut playing_info,
+        );
+
+        let names = |playlist_info: &PlaylistInfo| {
+            playlist_info
+                .songs
+                .iter()
+                .map(|song| song.song_name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(&playlist_info), ["song1", "song2", "song0", "song3"]);
+        assert_eq!(
+            names(&PlaylistInfo::load("move_keeps_current_song").unwrap()),
+            ["song1", "song2", "song0", "song3"]
+        );
+        assert_eq!(playing_info.currently_playing.load(Ordering::SeqCst), 0);
+        assert_eq!(playing_info.randomized_indices, [2, 0, 1, 3]);
+    }
+
+    #[test]
+    fn move_after_delete_is_refused() {
+        let mut output = NullOutput::default();
+        let mut playlist_info = playlist("move_after_delete_is_refused", 3);
+        let mut playing_info = playing_info(3);
+
+        for message in [Message::Delete(2), Message::Move { from: 2, to: 0 }] {
+            PlayMenu::handle_msg(message, &mut output, &mut playlist_info, &mut playing_info);
+        }
+
+        let names = playlist_info
+            .songs
+            .iter()
+            .map(|song| song.song_name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["song0", "song1"]);
+        assert!(playing_info.error.is_some());
+    }
+
+    #[test]
+    fn stats_go_to_the_song_that_was_played() {
+        let mut playlist_info = playlist("stats_go_to_the_song_that_was_played", 2);
+        let mut playing_info = playing_info(2);
+
+        // chapters of the same video share the file
+        for (index, song) in playlist_info.songs.iter_mut().enumerate() {
+            song.path_to_song = PathBuf::from("video.wav");
+            song.start_at = Some(index as f64 * 60.0);
+            song.id = format!("chapter{}", index);
+        }
+
+        let played = playlist_info.songs[0].clone();
+        playlist_info.move_song(0, 1);
+        PlayMenu::record_stats(&mut playlist_info, 0, &played, &mut playing_info, false);
+
+        let play_counts = playlist_info
+            .songs
+            .iter()
+            .map(|song| (song.id.as_str(), song.play_count))
+            .collect::<Vec<_>>();
+        assert_eq!(play_counts, [("chapter1", 0), ("chapter0", 1)]);
+    }
+
+    #[test]
+    fn moved_indices() {
+        // moved forward, the songs in between go back by one
+        assert_eq!(moved_index(0, 0, 2), 2);
+        assert_eq!(moved_index(1, 0, 2), 0);
+        assert_eq!(moved_index(2, 0, 2), 1);
+        assert_eq!(moved_index(3, 0, 2), 3);
+
+        // moved back, the songs in between go forward by one
+        assert_eq!(moved_index(3, 3, 1), 1);
+        assert_eq!(moved_index(1, 3, 1), 2);
+        assert_eq!(moved_index(2, 3, 1), 3);
+        assert_eq!(moved_index(0, 3, 1), 0);
+
+        assert_eq!(moved_index(2, 2, 2), 2);
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let mut output = NullOutput::default();
+        let mut playlist_info = playlist("pause_and_resume", 1);
+        let mut playing_info = playing_info(1);
+
+        output.play(&silent_wav(1.0));
+
+        for (message, paused) in [
+            (Message::Pause, true),
+            (Message::Resume, false),
+            (Message::PauseOrResume, true),
+        ] {
+            let instruction =
+                PlayMenu::handle_msg(message, &mut output, &mut playlist_info, &mut playing_info);
+
+            assert_eq!(instruction, SongInstruction::None);
+            assert_eq!(output.paused, paused);
+            assert_eq!(playing_info.is_paused.get(), paused);
+        }
+    }
+
+    #[test]
+    fn volume_uses_multiplier() {
+        let mut output = NullOutput::default();
+        let mut playlist_info = playlist("volume_uses_multiplier", 2);
+        let playing_info = playing_info(2);
+
+        playlist_info.songs[0].sound_multiplier = 2.0;
+        PlayMenu::update_volume(&mut output, &playlist_info, &playing_info);
+
+        assert_eq!(
+            output.volume,
+            multiplied_volume(SETTINGS.read().volume, 2.0)
+        );
+    }
+
+    #[test]
+    fn recv_cmd_stops_on_message() {
+        let mut output = NullOutput::default();
+        let playlist_info = RwLock::new(playlist("recv_cmd_stops_on_message", 2));
+        let mut playing_info = playing_info(2);
+        let (sender, receiver) = mpsc::channel();
+
+        output.play(&silent_wav(5.0));
+        sender.send(Message::PlayNext).unwrap();
+
+        let instruction =
+            PlayMenu::recv_cmd(&mut output, &receiver, &playlist_info, &mut playing_info);
+
+        assert_eq!(instruction, SongInstruction::SkipLoop);
+        assert_eq!(playing_info.currently_playing.load(Ordering::SeqCst), 1);
+        assert!(output.is_playing());
+    }
+
+    /// play the current song to its end, then move on like the player does
+    fn play_to_end(
+        playback_mode: PlaybackMode,
+        playlist_info: &RwLock<PlaylistInfo>,
+        playing_info: &mut PlayingInfo,
+    ) -> Option<usize> {
+        let mut output = NullOutput::default();
+        let (_sender, receiver) = mpsc::channel();
+
+        output.play(&silent_wav(0.02));
+        let instruction = PlayMenu::recv_cmd(&mut output, &receiver, playlist_info, playing_info);
+        assert_eq!(instruction, SongInstruction::None);
+
+        PlayMenu::next_song(playback_mode, &playlist_info.read(), playing_info)
+            .then(|| playing_info.currently_playing.load(Ordering::SeqCst))
+    }
+
+    #[test]
+    fn next_song_in_sequel_mode() {
+        let playlist_info = RwLock::new(playlist("next_song_in_sequel_mode", 3));
+        let mut playing_info = playing_info(3);
+
+        let played = (0..3)
+            .map(|_| play_to_end(PlaybackMode::Sequel, &playlist_info, &mut playing_info))
+            .collect::<Vec<_>>();
+
+        // the playlist ends after the last song
+        assert_eq!(played, [Some(1), Some(2), None]);
+    }
+
+    #[test]
+    fn next_song_in_loop_modes() {
+        let playlist_info = RwLock::new(playlist("next_song_in_loop_modes", 2));
+        let mut playing_info = playing_info(2);
+
+        let played = (0..3)
+            .map(|_| {
+                play_to_end(
+                    PlaybackMode::LoopPlaylist,
+                    &playlist_info,
+                    &mut playing_info,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(played, [Some(1), Some(0), Some(1)]);
+
+        let played = (0..2)
+            .map(|_| play_to_end(PlaybackMode::LoopOnce, &playlist_info, &mut playing_info))
+            .collect::<Vec<_>>();
+        assert_eq!(played, [Some(1), Some(1)]);
+    }
+
+    #[test]
+    fn next_song_in_random_mode() {
+        let playlist_info = RwLock::new(playlist("next_song_in_random_mode", 4));
+        let mut playing_info = playing_info(4);
+
+        // every song is played once before any song is played again
+        let played = (0..4)
+            .filter_map(|_| play_to_end(PlaybackMode::Random, &playlist_info, &mut playing_info))
+            .collect::<HashSet<_>>();
+        assert_eq!(played, HashSet::from([0, 1, 2, 3]));
+
+        // then the songs are shuffled again
+        let next = play_to_end(PlaybackMode::Random, &playlist_info, &mut playing_info);
+        assert!(next.is_some_and(|index| index < 4));
+        assert_eq!(playing_info.randomized_indices.len(), 3);
+    }
+
+    #[test]
+    fn recv_cmd_returns_when_song_ends() {
+        let mut output = NullOutput::default();
+        let playlist_info = RwLock::new(playlist("recv_cmd_returns_when_song_ends", 2));
+        let mut playing_info = playing_info(2);
+        let (_sender, receiver) = mpsc::channel();
+
+        output.play(&silent_wav(0.05));
+
+        let instruction =
+            PlayMenu::recv_cmd(&mut output, &receiver, &playlist_info, &mut playing_info);
+
+        assert_eq!(instruction, SongInstruction::None);
+        assert!(playing_info.current_duration >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn trim_end_stops_the_song() {
+        let mut output = NullOutput::default();
+        let playlist_info = RwLock::new(playlist("trim_end_stops_the_song", 2));
+        let mut playing_info = playing_info(2);
+        let (sender, receiver) = mpsc::channel();
+
+        output.play(&silent_wav(5.0));
+        output.seek(Duration::from_millis(1500));
+        output.set_pause(true);
+        PlayMenu::handle_msg(
+            Message::Trim(TrimPoint::Start),
+            &mut output,
+            &mut playlist_info.write(),
+            &mut playing_info,
+        );
+
+        output.seek(Duration::from_millis(2500));
+        sender.send(Message::Trim(TrimPoint::End)).unwrap();
+
+        let instruction =
+            PlayMenu::recv_cmd(&mut output, &receiver, &playlist_info, &mut playing_info);
+
+        let song = &playlist_info.read().songs[0];
+        assert_eq!(instruction, SongInstruction::None);
+        assert_eq!(song.start_at, Some(1.5));
+        assert_eq!(song.end_at, Some(2.5));
+        assert!(!output.is_playing());
+    }
+
+    #[test]
+    fn trim_to_nothing_is_refused() {
+        let mut output = NullOutput::default();
+        let mut playlist_info = playlist("trim_to_nothing_is_refused", 1);
+        let mut playing_info = playing_info(1);
+        playlist_info.songs[0].start_at = Some(2.0);
+        playlist_info.songs[0].end_at = Some(3.0);
+
+        output.play(&silent_wav(5.0));
+
+        for (position, point) in [(1500, TrimPoint::End), (3500, TrimPoint::Start)] {
+            output.seek(Duration::from_millis(position));
+            PlayMenu::handle_msg(
+                Message::Trim(point),
+                &mut output,
+                &mut playlist_info,
+                &mut playing_info,
+            );
+
+            assert!(playing_info.error.take().is_some());
+        }
+
+        let song = &playlist_info.songs[0];
+        assert_eq!((song.start_at, song.end_at), (Some(2.0), Some(3.0)));
+        assert_eq!(playing_info.end_at, None);
+    }
 
     #[test]
-    fn voice_count() -> anyhow::Result<()> {
-        let sl = Soloud::default()?;
+    fn recv_cmd_stops_at_end_of_song() {
+        let mut output = NullOutput::default();
+        let playlist_info = RwLock::new(playlist("recv_cmd_stops_at_end_of_song", 2));
+        let mut playing_info = playing_info(2);
+        let (_sender, receiver) = mpsc::channel();
 
-        let mut wav = Wav::default();
-        wav.load("JJD - Adventure [NCS Release].wav")?;
+        playing_info.start_at = Duration::from_secs(2);
+        playing_info.end_at = Some(Duration::from_millis(2050));
 
-        sl.play(&wav);
+        output.play(&silent_wav(5.0));
+        output.seek(playing_info.start_at);
 
-        println!("active voice count: {}", wav.length());
+        let instruction =
+            PlayMenu::recv_cmd(&mut output, &receiver, &playlist_info, &mut playing_info);
 
-        Ok(())
+        assert_eq!(instruction, SongInstruction::None);
+        assert!(!output.is_playing());
+        assert!(playing_info.current_duration < Duration::from_secs(1));
     }
 }
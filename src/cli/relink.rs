@@ -0,0 +1,109 @@
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+
+use crate::util::{library, print_missing};
+
+use super::data::{PlaylistInfo, Song};
+
+#[derive(Args)]
+pub struct Relink {
+    /// The playlist to repair
+    playlist_name: String,
+
+    /// The old start of the paths of the songs
+    #[clap(long, requires = "to", conflicts_with = "search")]
+    from: Option<PathBuf>,
+
+    /// The new start of the paths of the songs
+    #[clap(long, requires = "from")]
+    to: Option<PathBuf>,
+
+    /// Look for the songs that can't be found in this folder and its
+    /// subfolders by their file name
+    #[clap(short, long, required_unless_present = "from")]
+    search: Option<PathBuf>,
+
+    /// Only show what would be changed
+    #[clap(long, action)]
+    dry_run: bool,
+}
+
+impl Relink {
+    pub fn handle(&self) {
+        let mut playlist_info = PlaylistInfo::load_or_exit(&self.playlist_name);
+
+        let changed = match (&self.from, &self.to, &self.search) {
+            (Some(from), Some(to), _) => Self::replace_prefix(&mut playlist_info.songs, from, to),
+            (_, _, Some(folder)) => Self::search(&mut playlist_info.songs, folder),
+            _ => unreachable!("clap makes sure either --from and --to or --search is given"),
+        };
+
+        print_missing(playlist_info.missing_songs(), "songs still can't be found");
+
+        if self.dry_run {
+            println!("Would relink {} songs. Nothing was changed", changed);
+            return;
+        }
+
+        if changed > 0 {
+            playlist_info.save_or_exit();
+        }
+
+        println!("Relinked {} songs", changed);
+    }
+
+    /// returns how many songs were changed
+    fn replace_prefix(songs: &mut [Song], from: &Path, to: &Path) -> usize {
+        let mut changed = 0;
+
+        for song in songs {
+            if let Some(path) = library::replace_prefix(&song.path(), from, to) {
+                Self::relink(song, path);
+                changed += 1;
+            }
+        }
+
+        changed
+    }
+
+    /// returns how many songs were found
+    fn search(songs: &mut [Song], folder: &Path) -> usize {
+        let files = library::files_by_name(folder);
+        let mut changed = 0;
+
+        for song in songs.iter_mut().filter(|song| !song.path().exists()) {
+            let found = song
+                .path()
+                .file_name()
+                .and_then(|file_name| files.get(file_name));
+
+            match found.map(|paths| paths.as_slice()) {
+                Some([path]) => {
+                    Self::relink(song, path.clone());
+                    changed += 1;
+                }
+                Some(paths) => {
+                    println!(
+                        r#"Found {} files for "{}", skipped it:"#,
+                        paths.len(),
+                        song.song_name
+                    );
+
+                    for path in paths {
+                        println!("    {}", path.display());
+                    }
+                }
+                None => {}
+            }
+        }
+
+        changed
+    }
+
+    fn relink(song: &mut Song, path: PathBuf) {
+        println!("{} -> {}", song.path().display(), path.display());
+
+        song.path_to_song = library::to_stored(&path);
+    }
+}
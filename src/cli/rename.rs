@@ -1,8 +1,6 @@
-use std::fs;
-
 use clap::Args;
 
-use crate::util::playlist_info_folder;
+use crate::util::storage::storage;
 
 #[derive(Args)]
 pub struct Rename {
@@ -15,10 +13,7 @@ pub struct Rename {
 
 impl Rename {
     pub fn handle(&self) {
-        let path = playlist_info_folder(&self.playlist_name);
-        let new_path = playlist_info_folder(&self.new_name);
-
-        if let Err(e) = fs::rename(path, new_path) {
+        if let Err(e) = storage().rename_playlist(&self.playlist_name, &self.new_name) {
             println!("Failed to rename! Error: {e}");
             return;
         }
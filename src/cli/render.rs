@@ -0,0 +1,81 @@
+use std::{path::PathBuf, process};
+
+use clap::Args;
+use hound::WavWriter;
+
+use crate::util::{
+    format_duration, multiplied_volume,
+    render::{self, Mixer},
+    settings::SETTINGS,
+};
+
+use super::data::PlaylistInfo;
+
+#[derive(Args)]
+pub struct Render {
+    /// The playlist to render
+    playlist_name: String,
+
+    /// The wav file to write the mix into
+    output: PathBuf,
+
+    /// Seconds to fade from one song into the next
+    #[clap(short, long, default_value_t = 0.0)]
+    crossfade: f32,
+}
+
+impl Render {
+    pub fn handle(&self) {
+        let playlist_info = PlaylistInfo::load_or_exit(&self.playlist_name);
+
+        if let Err(e) = self.render(&playlist_info) {
+            println!("Failed to render the playlist! Error: {:#}", e);
+            process::exit(1);
+        }
+    }
+
+    fn render(&self, playlist_info: &PlaylistInfo) -> anyhow::Result<()> {
+        let writer = WavWriter::create(&self.output, render::output_spec())?;
+        let mut mixer = Mixer::new(writer, self.crossfade);
+        let volume = SETTINGS.read().volume;
+
+        let mut skipped = 0;
+
+        for (index, song) in playlist_info.songs.iter().enumerate() {
+            println!(
+                "[{}/{}] {}",
+                index + 1,
+                playlist_info.songs.len(),
+                song.song_name
+            );
+
+            let samples = match render::decode_wav(&song.path()) {
+                Ok(v) => v,
+                Err(e) => {
+                    println!("    Skipped! Error: {:#}", e);
+                    skipped += 1;
+                    continue;
+                }
+            };
+
+            let samples = render::trim(&samples, song.start_offset(), song.end_offset());
+
+            mixer.add_track(samples, multiplied_volume(volume, song.sound_multiplier))?;
+        }
+
+        let seconds = mixer.finish()?;
+
+        println!(
+            "Rendered {} songs ({}) into {}",
+            playlist_info.songs.len() - skipped,
+            format_duration(seconds),
+            self.output.display()
+        );
+
+        if skipped > 0 {
+            println!("{} songs were skipped, see the errors above", skipped);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use std::{fs, process};
+
+use chrono::{DateTime, Local};
+use clap::Args;
+
+use crate::util::{
+    backup, playlist_info_path,
+    storage::{storage, StorageKind},
+};
+
+use super::data::PlaylistInfo;
+
+#[derive(Args)]
+pub struct Restore {
+    /// The playlist to roll back
+    playlist_name: String,
+
+    /// Which backup to restore. 1 is the newest one
+    #[clap(short, long, default_value_t = 1)]
+    backup: usize,
+
+    /// List the backups of the playlist instead of restoring one
+    #[clap(short, long, action)]
+    list: bool,
+}
+
+impl Restore {
+    pub fn handle(&self) {
+        if storage().kind() != StorageKind::Json {
+            println!(
+                "Backups are only kept when the playlists are stored in the json files, not in {}",
+                storage().kind()
+            );
+            process::exit(1);
+        }
+
+        let backups = backup::list_backups(&playlist_info_path(&self.playlist_name));
+
+        if backups.is_empty() {
+            println!(r#"There are no backups of "{}""#, self.playlist_name);
+            process::exit(1);
+        }
+
+        if self.list {
+            for (n, path) in backups {
+                let modified = fs::metadata(&path)
+                    .and_then(|metadata| metadata.modified())
+                    .map(|time| {
+                        DateTime::<Local>::from(time)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_else(|_| "Unknown".to_string());
+
+                println!("{}. saved at {}", n, modified);
+            }
+
+            return;
+        }
+
+        if let Err(e) = PlaylistInfo::restore(&self.playlist_name, self.backup) {
+            println!(
+                r#"Failed to restore "{}"! Error: {:#}"#,
+                self.playlist_name, e
+            );
+            println!("Use `--list` to see the backups of the playlist");
+            process::exit(1);
+        }
+
+        println!(
+            r#"Restored backup {} of "{}". The replaced version is now backup 1"#,
+            self.backup, self.playlist_name
+        );
+    }
+}
@@ -49,7 +49,7 @@ impl Search {
 
         // TODO: Download video & add to playlist
         let link = format!("https://www.youtube.com/watch?v={video_id}");
-        if let Err(e) = add_from_youtube_link(&self.add_to, &link) {
+        if let Err(e) = add_from_youtube_link(&self.add_to, &link, true) {
             println!("Failed to download video. Error: {e}");
         }
     }
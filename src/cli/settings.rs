@@ -1,6 +1,15 @@
-use clap::Args;
+use std::path::PathBuf;
 
-use crate::util::settings::{PlaybackMode, SETTINGS};
+use clap::{
+    builder::{OsStringValueParser, TypedValueParser},
+    Args,
+};
+
+use crate::util::{
+    audio::AudioBackend,
+    settings::{PlaybackMode, SETTINGS},
+    visualizer::VisualizerMode,
+};
 
 /// You can change settings using this subcommand
 #[derive(Args)]
@@ -25,6 +34,27 @@ pub struct ChangeSettings {
     /// computer's volume)
     #[clap(short, long)]
     volume: Option<u8>,
+
+    /// Set the visualizer shown while playing. Can be either of off, bars,
+    /// wave
+    #[clap(long)]
+    visualizer: Option<VisualizerMode>,
+
+    /// Set where the music is played. Can be either soloud (the sound card) or
+    /// null (no sound at all, useful on machines without a sound card)
+    #[clap(long)]
+    audio_backend: Option<AudioBackend>,
+
+    /// Set how many old versions of each playlist are kept. They can be
+    /// brought back with `music restore`
+    #[clap(long)]
+    backups: Option<usize>,
+
+    /// Set the folder song paths are stored relative to, so the library can
+    /// be moved by changing only this. Default is the home directory, which
+    /// an empty value goes back to
+    #[clap(long, value_parser = OsStringValueParser::new().map(PathBuf::from))]
+    library_root: Option<PathBuf>,
 }
 
 impl ChangeSettings {
@@ -41,9 +71,41 @@ impl ChangeSettings {
             is_settings_changed = true;
         }
 
+        if let Some(visualizer) = self.visualizer {
+            settings.visualizer = visualizer;
+            is_settings_changed = true;
+        }
+
+        if let Some(audio_backend) = self.audio_backend {
+            settings.audio_backend = audio_backend;
+            is_settings_changed = true;
+        }
+
+        if let Some(backups) = self.backups {
+            settings.backup_count = backups;
+            is_settings_changed = true;
+        }
+
+        if let Some(library_root) = &self.library_root {
+            settings.library_root =
+                Some(library_root.clone()).filter(|path| !path.as_os_str().is_empty());
+            is_settings_changed = true;
+        }
+
         if self.show_settings {
             println!("Playback mode --- {}", settings.playback_mode);
             println!("Volume --- {}", settings.volume);
+            println!("Visualizer --- {}", settings.visualizer);
+            println!("Audio backend --- {}", settings.audio_backend);
+            println!("Backups --- {}", settings.backup_count);
+            println!(
+                "Library root --- {}",
+                settings
+                    .library_root
+                    .as_ref()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_else(|| "Home directory".to_string())
+            );
         }
 
         if is_settings_changed {
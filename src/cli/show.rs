@@ -0,0 +1,393 @@
+use std::{cmp::Reverse, fmt::Display, process, str::FromStr};
+
+use anyhow::anyhow;
+use clap::Args;
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use serde_json::{json, Value};
+use termcolor::ColorSpec;
+
+use crate::util::{colored, date_or, format_duration, history::escape_csv};
+
+use super::data::{PlaylistInfo, Song};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortBy {
+    Plays,
+    Skips,
+    LastPlayed,
+    Added,
+}
+
+impl FromStr for SortBy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "plays" => Ok(Self::Plays),
+            "skips" => Ok(Self::Skips),
+            "lastplayed" | "last-played" => Ok(Self::LastPlayed),
+            "added" => Ok(Self::Added),
+            _ => Err(anyhow!(
+                "Unknown sort order! Valid ones are: plays, skips, last-played, added"
+            )),
+        }
+    }
+}
+
+impl Display for SortBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sort_by = match self {
+            Self::Plays => "plays",
+            Self::Skips => "skips",
+            Self::LastPlayed => "last-played",
+            Self::Added => "added",
+        };
+
+        write!(f, "{}", sort_by)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    Index,
+    Name,
+    Author,
+    Multiplier,
+    Duration,
+    Exists,
+    Path,
+    Plays,
+    Skips,
+    LastPlayed,
+    Added,
+    Tags,
+}
+
+impl FromStr for Column {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "index" => Ok(Self::Index),
+            "name" => Ok(Self::Name),
+            "author" => Ok(Self::Author),
+            "multiplier" => Ok(Self::Multiplier),
+            "duration" => Ok(Self::Duration),
+            "exists" => Ok(Self::Exists),
+            "path" => Ok(Self::Path),
+            "plays" => Ok(Self::Plays),
+            "skips" => Ok(Self::Skips),
+            "lastplayed" | "last-played" => Ok(Self::LastPlayed),
+            "added" => Ok(Self::Added),
+            "tags" => Ok(Self::Tags),
+            _ => Err(anyhow!(
+                "Unknown column! Valid ones are: index, name, author, multiplier, duration, exists, path, plays, skips, last-played, added, tags"
+            )),
+        }
+    }
+}
+
+impl Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let column = match self {
+            Self::Index => "index",
+            Self::Name => "name",
+            Self::Author => "author",
+            Self::Multiplier => "multiplier",
+            Self::Duration => "duration",
+            Self::Exists => "exists",
+            Self::Path => "path",
+            Self::Plays => "plays",
+            Self::Skips => "skips",
+            Self::LastPlayed => "last-played",
+            Self::Added => "added",
+            Self::Tags => "tags",
+        };
+
+        write!(f, "{}", column)
+    }
+}
+
+impl Column {
+    const DEFAULT: [Column; 7] = [
+        Self::Index,
+        Self::Name,
+        Self::Author,
+        Self::Multiplier,
+        Self::Duration,
+        Self::Exists,
+        Self::Path,
+    ];
+
+    /// the name of the column in the json and csv output
+    fn key(&self) -> &'static str {
+        match self {
+            Self::Index => "index",
+            Self::Name => "song_name",
+            Self::Author => "author",
+            Self::Multiplier => "sound_multiplier",
+            Self::Duration => "duration_secs",
+            Self::Exists => "exists",
+            Self::Path => "path",
+            Self::Plays => "play_count",
+            Self::Skips => "skip_count",
+            Self::LastPlayed => "last_played",
+            Self::Added => "date_added",
+            Self::Tags => "tags",
+        }
+    }
+
+    /// the name of the column in the table header
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Index => "#",
+            Self::Name => "Name",
+            Self::Author => "Author",
+            Self::Multiplier => "Multiplier",
+            Self::Duration => "Duration",
+            Self::Exists => "Exists",
+            Self::Path => "Path",
+            Self::Plays => "Plays",
+            Self::Skips => "Skips",
+            Self::LastPlayed => "Last played",
+            Self::Added => "Added",
+            Self::Tags => "Tags",
+        }
+    }
+
+    /// numbers are aligned to the right in the table
+    fn is_number(&self) -> bool {
+        matches!(
+            self,
+            Self::Index | Self::Multiplier | Self::Duration | Self::Plays | Self::Skips
+        )
+    }
+
+    /// the cell in the table and the csv output. The index starts at 1 like
+    /// in the player
+    fn text(&self, index: usize, song: &Song) -> String {
+        match self {
+            Self::Index => (index + 1).to_string(),
+            Self::Name => song.song_name.clone(),
+            Self::Author => song.author.clone().unwrap_or_default(),
+            Self::Multiplier => song.sound_multiplier.to_string(),
+            Self::Duration => song
+                .duration()
+                .map(|duration| format_duration(duration.as_secs()))
+                .unwrap_or_else(|| "unknown".to_string()),
+            Self::Exists => if song.path().exists() { "yes" } else { "no" }.to_string(),
+            Self::Path => song.path().display().to_string(),
+            Self::Plays => song.play_count.to_string(),
+            Self::Skips => song.skip_count.to_string(),
+            Self::LastPlayed => date_or(&song.last_played, "never"),
+            Self::Added => date_or(&song.date_added, "unknown"),
+            Self::Tags => song.tags.join(", "),
+        }
+    }
+
+    fn json(&self, index: usize, song: &Song) -> Value {
+        match self {
+            Self::Index => json!(index + 1),
+            Self::Name => json!(song.song_name),
+            Self::Author => json!(song.author),
+            Self::Multiplier => json!(song.sound_multiplier),
+            Self::Duration => json!(song.duration().map(|duration| duration.as_secs())),
+            Self::Exists => json!(song.path().exists()),
+            Self::Path => json!(song.path()),
+            Self::Plays => json!(song.play_count),
+            Self::Skips => json!(song.skip_count),
+            Self::LastPlayed => json!(song.last_played),
+            Self::Added => json!(song.date_added),
+            Self::Tags => json!(song.tags),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct Show {
+    /// The playlist to show
+    playlist_name: String,
+
+    /// Sort the songs, most first. Can be either of plays, skips, last-played,
+    /// added
+    #[clap(short, long)]
+    sort: Option<SortBy>,
+
+    /// The columns to show, separated by commas. Can be any of index, name,
+    /// author, multiplier, duration, exists, path, plays, skips, last-played,
+    /// added, tags
+    #[clap(
+        short,
+        long,
+        value_delimiter = ',',
+        default_values_t = Column::DEFAULT
+    )]
+    columns: Vec<Column>,
+
+    /// Print the songs as json
+    #[clap(long, action, conflicts_with = "csv")]
+    json: bool,
+
+    /// Print the songs as csv
+    #[clap(long, action)]
+    csv: bool,
+}
+
+impl Show {
+    pub fn handle(&self) {
+        let playlist_info = PlaylistInfo::load_or_exit(&self.playlist_name);
+
+        let mut songs = playlist_info.songs.iter().enumerate().collect::<Vec<_>>();
+
+        if let Some(sort) = self.sort {
+            sort_songs(&mut songs, sort);
+        }
+
+        if self.json {
+            self.print_json(&songs);
+        } else if self.csv {
+            self.print_csv(&songs);
+        } else {
+            colored::writeln(
+                ColorSpec::new().set_bold(true),
+                &format!("Playlist: {}", playlist_info.name),
+            );
+            println!();
+
+            self.print_table(&songs);
+        }
+    }
+
+    fn print_table(&self, songs: &[(usize, &Song)]) {
+        let rows = songs
+            .iter()
+            .map(|(index, song)| {
+                self.columns
+                    .iter()
+                    .map(|column| column.text(*index, song))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let widths = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([column.title().chars().count()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        let format_row = |cells: &[&str]| {
+            self.columns
+                .iter()
+                .zip(cells)
+                .zip(&widths)
+                .map(|((column, cell), &width)| {
+                    if column.is_number() {
+                        format!("{:>width$}", cell)
+                    } else {
+                        format!("{:<width$}", cell)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+
+        let titles = self.columns.iter().map(Column::title).collect::<Vec<_>>();
+        colored::writeln(ColorSpec::new().set_bold(true), &format_row(&titles));
+
+        for row in &rows {
+            println!(
+                "{}",
+                format_row(&row.iter().map(String::as_str).collect::<Vec<_>>())
+            );
+        }
+    }
+
+    fn print_json(&self, songs: &[(usize, &Song)]) {
+        let rows = songs
+            .iter()
+            .map(|&(index, song)| JsonRow {
+                columns: &self.columns,
+                index,
+                song,
+            })
+            .collect::<Vec<_>>();
+
+        match serde_json::to_string_pretty(&rows) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                println!("Failed to write the json! Error: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    fn print_csv(&self, songs: &[(usize, &Song)]) {
+        let header = self.columns.iter().map(Column::key).collect::<Vec<_>>();
+        println!("{}", header.join(","));
+
+        for (index, song) in songs {
+            let row = self
+                .columns
+                .iter()
+                .map(|column| escape_csv(&column.text(*index, song)))
+                .collect::<Vec<_>>();
+
+            println!("{}", row.join(","));
+        }
+    }
+}
+
+/// a song in the json output, with the fields in the order of the columns
+struct JsonRow<'a> {
+    columns: &'a [Column],
+    index: usize,
+    song: &'a Song,
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+
+        for column in self.columns {
+            map.serialize_entry(column.key(), &column.json(self.index, self.song))?;
+        }
+
+        map.end()
+    }
+}
+
+/// sort the songs with the biggest value first. Songs without the value go
+/// to the end
+pub fn sort_songs(songs: &mut [(usize, &Song)], sort: SortBy) {
+    match sort {
+        SortBy::Plays => songs.sort_by_key(|(_, song)| Reverse(song.play_count)),
+        SortBy::Skips => songs.sort_by_key(|(_, song)| Reverse(song.skip_count)),
+        SortBy::LastPlayed => {
+            songs.sort_by_key(|(_, song)| Reverse(song.last_played.as_ref().map(|t| t.0)))
+        }
+        SortBy::Added => {
+            songs.sort_by_key(|(_, song)| Reverse(song.date_added.as_ref().map(|t| t.0)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Column;
+
+    #[test]
+    fn columns_parse() {
+        assert_eq!("Last-Played".parse::<Column>().unwrap(), Column::LastPlayed);
+        assert_eq!(" path".parse::<Column>().unwrap(), Column::Path);
+        assert!("size".parse::<Column>().is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use std::process;
+
+use clap::{Args, Subcommand};
+use termcolor::ColorSpec;
+
+use crate::util::{colored, date_or, format_duration, get_index};
+
+use super::data::{PlaylistInfo, Song};
+
+#[derive(Args)]
+pub struct SongCommand {
+    #[clap(subcommand)]
+    command: SongSubcommand,
+}
+
+#[derive(Subcommand)]
+enum SongSubcommand {
+    /// Remove a song from a playlist
+    Rm {
+        /// The playlist the song is in
+        playlist_name: String,
+
+        /// The index of the song. Negative value starts from the back
+        #[clap(allow_hyphen_values = true)]
+        index: i32,
+    },
+
+    /// Move a song to another position in the playlist
+    Mv {
+        /// The playlist the song is in
+        playlist_name: String,
+
+        /// The index of the song. Negative value starts from the back
+        #[clap(allow_hyphen_values = true)]
+        index: i32,
+
+        /// The index the song ends up at. Negative value starts from the back
+        #[clap(allow_hyphen_values = true)]
+        to: i32,
+    },
+
+    /// Rename a song
+    Rename {
+        /// The playlist the song is in
+        playlist_name: String,
+
+        /// The index of the song. Negative value starts from the back
+        #[clap(allow_hyphen_values = true)]
+        index: i32,
+
+        /// The new name of the song
+        #[clap(required = true)]
+        new_name: Vec<String>,
+    },
+
+    /// Set the volume multiplier of a song
+    SetMultiplier {
+        /// The playlist the song is in
+        playlist_name: String,
+
+        /// The index of the song. Negative value starts from the back
+        #[clap(allow_hyphen_values = true)]
+        index: i32,
+
+        /// Multiplies the volume while the song plays. Must be a non-negative
+        /// real number
+        multiplier: f32,
+    },
+
+    /// Show everything about a song
+    Info {
+        /// The playlist the song is in
+        playlist_name: String,
+
+        /// The index of the song. Negative value starts from the back
+        #[clap(allow_hyphen_values = true)]
+        index: i32,
+    },
+}
+
+impl SongCommand {
+    pub fn handle(&self) {
+        match &self.command {
+            SongSubcommand::Rm {
+                playlist_name,
+                index,
+            } => Self::remove(playlist_name, *index),
+            SongSubcommand::Mv {
+                playlist_name,
+                index,
+                to,
+            } => Self::move_song(playlist_name, *index, *to),
+            SongSubcommand::Rename {
+                playlist_name,
+                index,
+                new_name,
+            } => Self::rename(playlist_name, *index, &new_name.join(" ")),
+            SongSubcommand::SetMultiplier {
+                playlist_name,
+                index,
+                multiplier,
+            } => Self::set_multiplier(playlist_name, *index, *multiplier),
+            SongSubcommand::Info {
+                playlist_name,
+                index,
+            } => Self::info(playlist_name, *index),
+        }
+    }
+
+    /// load the playlist and find the song in it, or exit the program
+    fn load(playlist_name: &str, index: i32) -> (PlaylistInfo, usize) {
+        let playlist_info = PlaylistInfo::load_or_exit(playlist_name);
+
+        let index = Self::index(index, &playlist_info);
+
+        (playlist_info, index)
+    }
+
+    fn index(index: i32, playlist_info: &PlaylistInfo) -> usize {
+        match get_index(index, playlist_info.songs.len()) {
+            Ok(i) => i,
+            Err(e) => {
+                println!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    fn remove(playlist_name: &str, index: i32) {
+        let (mut playlist_info, index) = Self::load(playlist_name, index);
+        let song_name = playlist_info.songs[index].song_name.clone();
+
+        if let Err(e) = playlist_info.remove_song(index) {
+            println!("{}", playlist_info.failed("save", &e));
+            process::exit(1);
+        }
+
+        println!(r#"Removed "{}" from "{}""#, song_name, playlist_name);
+    }
+
+    fn move_song(playlist_name: &str, index: i32, to: i32) {
+        let (mut playlist_info, from) = Self::load(playlist_name, index);
+        let to = Self::index(to, &playlist_info);
+
+        // saving merges in the songs other processes removed, so the song
+        // might not be at `to` anymore afterwards
+        let song_name = playlist_info.songs[from].song_name.clone();
+
+        playlist_info.move_song(from, to);
+        playlist_info.save_or_exit();
+
+        println!(r#"Moved "{}" from {} to {}"#, song_name, from + 1, to + 1);
+    }
+
+    fn rename(playlist_name: &str, index: i32, new_name: &str) {
+        let new_name = new_name.trim();
+
+        if new_name.is_empty() {
+            println!("The new name can't be empty!");
+            process::exit(1);
+        }
+
+        let (mut playlist_info, index) = Self::load(playlist_name, index);
+        let old_name = std::mem::replace(
+            &mut playlist_info.songs[index].song_name,
+            new_name.to_string(),
+        );
+
+        playlist_info.save_or_exit();
+
+        println!(r#"Renamed "{}" to "{}""#, old_name, new_name);
+    }
+
+    fn set_multiplier(playlist_name: &str, index: i32, multiplier: f32) {
+        if !multiplier.is_finite() || multiplier < 0.0 {
+            println!("The multiplier must be a non-negative real number!");
+            process::exit(1);
+        }
+
+        let (mut playlist_info, index) = Self::load(playlist_name, index);
+        let song = &mut playlist_info.songs[index];
+        song.sound_multiplier = multiplier;
+        let song_name = song.song_name.clone();
+
+        playlist_info.save_or_exit();
+
+        println!(
+            r#"Volume multiplier of "{}" is now {}"#,
+            song_name, multiplier
+        );
+    }
+
+    fn info(playlist_name: &str, index: i32) {
+        let (playlist_info, index) = Self::load(playlist_name, index);
+        let song = &playlist_info.songs[index];
+
+        colored::writeln(
+            ColorSpec::new().set_bold(true),
+            &format!("{}. {}", index + 1, song.song_name),
+        );
+
+        for (label, value) in Self::fields(song) {
+            println!("{:>12}: {}", label, value);
+        }
+    }
+
+    fn fields(song: &Song) -> Vec<(&'static str, String)> {
+        let path = song.path();
+        let or_none = |value: Option<String>| value.unwrap_or_else(|| "none".to_string());
+
+        vec![
+            ("Id", song.id.clone()),
+            ("Author", or_none(song.author.clone())),
+            (
+                "Path",
+                format!(
+                    "{}{}",
+                    path.display(),
+                    if path.exists() { "" } else { " (missing)" }
+                ),
+            ),
+            (
+                "Duration",
+                song.duration()
+                    .map(|duration| format_duration(duration.as_secs()))
+                    .unwrap_or_else(|| "unknown".to_string()),
+            ),
+            (
+                "Starts at",
+                or_none(song.start_at.map(|s| format!("{}s", s))),
+            ),
+            ("Ends at", or_none(song.end_at.map(|s| format!("{}s", s)))),
+            ("Multiplier", song.sound_multiplier.to_string()),
+            ("Played", song.play_count.to_string()),
+            ("Skipped", song.skip_count.to_string()),
+            ("Last played", date_or(&song.last_played, "never")),
+            ("Added", date_or(&song.date_added, "unknown")),
+            ("Rating", or_none(song.rating.map(|r| format!("{}/5", r)))),
+            (
+                "Favorite",
+                if song.favorite { "yes" } else { "no" }.to_string(),
+            ),
+            ("Tags", song.tags.join(", ")),
+            (
+                "Lyrics",
+                or_none(song.lyrics_path.as_ref().map(|p| p.display().to_string())),
+            ),
+        ]
+    }
+}
@@ -0,0 +1,133 @@
+use std::{cmp::Reverse, fmt::Display, str::FromStr};
+
+use anyhow::anyhow;
+use clap::Args;
+
+use crate::util::sort_by;
+
+use super::data::{PlaylistInfo, Song};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortField {
+    Name,
+    Author,
+    Duration,
+    Added,
+    Plays,
+}
+
+impl FromStr for SortField {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "name" => Ok(Self::Name),
+            "author" => Ok(Self::Author),
+            "duration" => Ok(Self::Duration),
+            "added" => Ok(Self::Added),
+            "plays" => Ok(Self::Plays),
+            _ => Err(anyhow!(
+                "Unknown field! Valid ones are: name, author, duration, added, plays"
+            )),
+        }
+    }
+}
+
+impl Display for SortField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let field = match self {
+            Self::Name => "name",
+            Self::Author => "author",
+            Self::Duration => "duration",
+            Self::Added => "added",
+            Self::Plays => "plays",
+        };
+
+        write!(f, "{}", field)
+    }
+}
+
+#[derive(Args)]
+pub struct Sort {
+    /// The playlist to sort
+    playlist_name: String,
+
+    /// What to sort the songs by. Names and authors go from a to z, durations
+    /// from the shortest, added from the oldest and plays from the most played.
+    /// Can be either of name, author, duration, added, plays
+    #[clap(short, long)]
+    by: SortField,
+
+    /// Reverse the order
+    #[clap(short, long, action)]
+    reverse: bool,
+}
+
+impl Sort {
+    pub fn handle(&self) {
+        let mut playlist_info = PlaylistInfo::load_or_exit(&self.playlist_name);
+
+        sort_songs(&mut playlist_info.songs, self.by, self.reverse);
+
+        playlist_info.save_or_exit();
+
+        println!(
+            r#"Sorted "{}" by {}{}"#,
+            self.playlist_name,
+            self.by,
+            if self.reverse { " in reverse" } else { "" }
+        );
+    }
+}
+
+/// sort the songs in the order described in `Sort::by`, or the other way
+/// around if `reverse` is set. Songs without the value go to the end either
+/// way, and songs with the same value keep their order
+fn sort_songs(songs: &mut [Song], by: SortField, reverse: bool) {
+    match by {
+        SortField::Name => sort_by(songs, reverse, |song| Some(song.song_name.to_lowercase())),
+        SortField::Author => sort_by(songs, reverse, |song| {
+            song.author.as_ref().map(|author| author.to_lowercase())
+        }),
+        SortField::Duration => sort_by(songs, reverse, Song::duration),
+        SortField::Added => sort_by(songs, reverse, |song| song.date_added.as_ref().map(|t| t.0)),
+        SortField::Plays => sort_by(songs, reverse, |song| Some(Reverse(song.play_count))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::cli::data::Song;
+
+    use super::{sort_songs, SortField};
+
+    fn names(songs: &[Song]) -> Vec<&str> {
+        songs.iter().map(|song| song.song_name.as_str()).collect()
+    }
+
+    #[test]
+    fn sort_songs_by_field() {
+        let mut songs = [("b", Some("Zed"), 3), ("C", None, 7), ("a", Some("amy"), 3)].map(
+            |(name, author, play_count)| Song {
+                play_count,
+                ..Song::new(
+                    name.to_string(),
+                    PathBuf::from(name),
+                    author.map(str::to_string),
+                    1.0,
+                )
+            },
+        );
+
+        sort_songs(&mut songs, SortField::Name, false);
+        assert_eq!(names(&songs), ["a", "b", "C"]);
+
+        sort_songs(&mut songs, SortField::Author, false);
+        assert_eq!(names(&songs), ["a", "b", "C"]);
+
+        sort_songs(&mut songs, SortField::Plays, false);
+        assert_eq!(names(&songs), ["C", "a", "b"]);
+    }
+}
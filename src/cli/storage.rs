@@ -0,0 +1,268 @@
+use std::process;
+
+use clap::{Args, Subcommand};
+
+use crate::util::storage::storage;
+#[cfg(feature = "sqlite")]
+use std::path::Path;
+
+#[cfg(feature = "sqlite")]
+use crate::util::{
+    json_storage::JsonStorage,
+    migration,
+    sqlite_storage::SqliteStorage,
+    storage::{Storage, StorageKind},
+};
+
+#[cfg(feature = "sqlite")]
+use super::data::PlaylistInfo;
+
+#[derive(Args)]
+pub struct StorageCommand {
+    #[clap(subcommand)]
+    command: StorageSubcommand,
+}
+
+#[derive(Subcommand)]
+enum StorageSubcommand {
+    /// Show where the playlists are stored
+    Status,
+
+    /// Copy the playlists, songs and settings from the json files into a
+    /// sqlite database and use it from now on
+    Import,
+
+    /// Copy everything from the database back into the json files and stop
+    /// using the database
+    Export,
+}
+
+impl StorageCommand {
+    pub fn handle(&self) {
+        match self.command {
+            StorageSubcommand::Status => Self::status(),
+            StorageSubcommand::Import => Self::import(),
+            StorageSubcommand::Export => Self::export(),
+        }
+    }
+
+    fn status() {
+        println!("The playlists are stored in {}", storage().kind());
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn import() {
+        if storage().kind() != StorageKind::Json {
+            println!("The playlists are already stored in the database!");
+            process::exit(1);
+        }
+
+        let result = (|| -> anyhow::Result<(usize, usize)> {
+            // playlists from before the song library have their songs moved
+            // into it first, so only ids are copied
+            for name in storage().playlist_names()? {
+                PlaylistInfo::load(&name)?;
+            }
+
+            import_into(&JsonStorage, &SqliteStorage::path())
+        })();
+
+        match result {
+            Ok((playlists, songs)) => println!(
+                "Imported {} playlists and {} songs into {}",
+                playlists,
+                songs,
+                SqliteStorage::path().display()
+            ),
+            Err(e) => {
+                println!("Failed to import into the database! Error: {:#}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn export() {
+        if storage().kind() != StorageKind::Sqlite {
+            println!("The playlists are not stored in the database!");
+            process::exit(1);
+        }
+
+        let result = export_from(storage(), &SqliteStorage::path(), &JsonStorage);
+
+        match result {
+            Ok((playlists, songs)) => println!(
+                "Exported {} playlists and {} songs into the json files",
+                playlists, songs
+            ),
+            Err(e) => {
+                println!("Failed to export the database! Error: {:#}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn import() {
+        Self::no_sqlite()
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn export() {
+        Self::no_sqlite()
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn no_sqlite() {
+        println!("This build has no sqlite support! Build it with `--features sqlite`");
+        process::exit(1);
+    }
+}
+
+/// copy everything into a new database at `path`. It is written next to it
+/// first, so a failed import leaves no database behind
+#[cfg(feature = "sqlite")]
+fn import_into(from: &dyn Storage, path: &Path) -> anyhow::Result<(usize, usize)> {
+    let temp_path = path.with_extension("db.tmp");
+    let _ = std::fs::remove_file(&temp_path);
+
+    let counts = copy(from, &SqliteStorage::open(&temp_path)?)?;
+    std::fs::rename(&temp_path, path)?;
+
+    Ok(counts)
+}
+
+/// copy everything from the database at `path` into `to`. The database is
+/// kept, but it is renamed so it is not used anymore
+#[cfg(feature = "sqlite")]
+fn export_from(
+    database: &dyn Storage,
+    path: &Path,
+    to: &dyn Storage,
+) -> anyhow::Result<(usize, usize)> {
+    let counts = copy(database, to)?;
+    std::fs::rename(path, path.with_extension("db.exported"))?;
+
+    Ok(counts)
+}
+
+/// copy everything from one storage into another. The history of the other
+/// storage is replaced, since it only has what was played before the last
+/// copy. Returns how many playlists and songs were copied
+#[cfg(feature = "sqlite")]
+fn copy(from: &dyn Storage, to: &dyn Storage) -> anyhow::Result<(usize, usize)> {
+    let songs = from.read_songs()?;
+    to.write_songs(&songs)?;
+
+    let names = from.playlist_names()?;
+
+    for name in &names {
+        let playlist = migration::migrate_playlist(from.read_playlist(name)?)?;
+        to.write_playlist(name, &playlist)?;
+    }
+
+    if let Some(settings) = from.read_settings()? {
+        to.write_settings(&migration::migrate_settings(settings)?)?;
+    }
+
+    to.write_history(&from.read_history()?)?;
+
+    Ok((names.len(), songs.len()))
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use basic_quick_lib::time::LocalTime;
+    use chrono::Local;
+    use serde_json::json;
+
+    use crate::{
+        cli::data::{default_sound_multiplier, Song},
+        util::{history::PlayEvent, home_folder, sqlite_storage::SqliteStorage, storage::Storage},
+    };
+
+    use super::{copy, export_from, import_into};
+
+    /// a database with a song, a playlist and a played song in it
+    fn database(name: &str, playlist_name: &str) -> (SqliteStorage, PathBuf) {
+        let path = Path::new(&home_folder()).join(format!("{}.db", name));
+        let _ = std::fs::remove_file(&path);
+        let storage = SqliteStorage::open(&path).unwrap();
+
+        let mut song = Song::new(
+            "song".to_string(),
+            PathBuf::from("song.wav"),
+            None,
+            default_sound_multiplier(),
+        );
+        song.id = format!("{}-song", name);
+
+        storage.write_songs(&[song.clone()]).unwrap();
+        storage
+            .write_playlist(
+                playlist_name,
+                &json!({ "version": 3, "songs": [song.id], "created": null }),
+            )
+            .unwrap();
+        storage
+            .append_history(&PlayEvent {
+                song_name: song.song_name,
+                author: None,
+                path_to_song: song.path_to_song,
+                playlist: playlist_name.to_string(),
+                started: LocalTime(Local::now()),
+                seconds_listened: 10,
+                skipped: false,
+            })
+            .unwrap();
+
+        (storage, path)
+    }
+
+    #[test]
+    fn copy_everything() -> anyhow::Result<()> {
+        let (from, _) = database("copy-from", "copied");
+        let (to, _) = database("copy-to", "old");
+        from.write_settings(&json!({ "volume": 10 }))?;
+
+        assert_eq!(copy(&from, &to)?, (1, 1));
+
+        assert_eq!(
+            to.read_playlist("copied")?["songs"],
+            json!(["copy-from-song"])
+        );
+        assert_eq!(to.read_songs()?.len(), 2);
+        assert_eq!(to.read_settings()?.unwrap()["volume"], 10);
+        // the old history is replaced
+        let history = to.read_history()?;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].playlist, "copied");
+
+        Ok(())
+    }
+
+    #[test]
+    fn import_then_export() -> anyhow::Result<()> {
+        let (from, _) = database("import-from", "imported");
+        let path = Path::new(&home_folder()).join("imported.db");
+
+        import_into(&from, &path)?;
+        assert!(!path.with_extension("db.tmp").exists());
+
+        let imported = SqliteStorage::open(&path)?;
+        assert_eq!(imported.playlist_names()?, ["imported"]);
+
+        let (to, _) = database("export-to", "old");
+        export_from(&imported, &path, &to)?;
+        assert!(!path.exists());
+        assert!(path.with_extension("db.exported").exists());
+        assert_eq!(
+            to.read_playlist("imported")?["songs"],
+            json!(["import-from-song"])
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+use std::{collections::BTreeMap, process};
+
+use clap::{Args, Subcommand};
+
+use crate::util::{get_index, playlist_names};
+
+use super::data::{normalize_tag, PlaylistInfo};
+
+#[derive(Args)]
+pub struct Tag {
+    #[clap(subcommand)]
+    command: TagCommand,
+}
+
+#[derive(Subcommand)]
+enum TagCommand {
+    /// Add tags to a song
+    Add {
+        /// The playlist the song is in
+        playlist_name: String,
+
+        /// The index of the song. Negative value starts from the back
+        #[clap(allow_hyphen_values = true)]
+        index: i32,
+
+        /// The tags to add
+        #[clap(required = true)]
+        tags: Vec<String>,
+    },
+
+    /// Remove tags from a song
+    Remove {
+        /// The playlist the song is in
+        playlist_name: String,
+
+        /// The index of the song. Negative value starts from the back
+        #[clap(allow_hyphen_values = true)]
+        index: i32,
+
+        /// The tags to remove
+        #[clap(required = true)]
+        tags: Vec<String>,
+    },
+
+    /// List the tags of every song in a playlist, or every tag in every
+    /// playlist if no playlist is given
+    List {
+        /// The playlist to list the tags of
+        playlist_name: Option<String>,
+    },
+}
+
+impl Tag {
+    pub fn handle(&self) {
+        match &self.command {
+            TagCommand::Add {
+                playlist_name,
+                index,
+                tags,
+            } => Self::change_tags(playlist_name, *index, tags, true),
+            TagCommand::Remove {
+                playlist_name,
+                index,
+                tags,
+            } => Self::change_tags(playlist_name, *index, tags, false),
+            TagCommand::List {
+                playlist_name: Some(playlist_name),
+            } => Self::list_playlist(playlist_name),
+            TagCommand::List {
+                playlist_name: None,
+            } => Self::list_all(),
+        }
+    }
+
+    fn change_tags(playlist_name: &str, index: i32, tags: &[String], add: bool) {
+        let mut playlist_info = PlaylistInfo::load_or_exit(playlist_name);
+
+        let index = match get_index(index, playlist_info.songs.len()) {
+            Ok(i) => i,
+            Err(e) => {
+                println!("{}", e);
+                process::exit(1);
+            }
+        };
+
+        let song = &mut playlist_info.songs[index];
+
+        for tag in tags {
+            let changed = if add {
+                song.add_tag(tag)
+            } else {
+                song.remove_tag(tag)
+            };
+
+            if !changed {
+                let reason = if add { "already has" } else { "does not have" };
+                println!(r#""{}" {} tag "{}""#, song.song_name, reason, tag);
+            }
+        }
+
+        println!("Tags of \"{}\": {}", song.song_name, song.tags.join(", "));
+        playlist_info.save_or_exit();
+    }
+
+    fn list_playlist(playlist_name: &str) {
+        let playlist_info = PlaylistInfo::load_or_exit(playlist_name);
+
+        for (index, song) in playlist_info.songs.iter().enumerate() {
+            println!(
+                "{}. {} [{}]",
+                index + 1,
+                song.song_name,
+                song.tags.join(", ")
+            );
+        }
+    }
+
+    fn list_all() {
+        let names = match playlist_names() {
+            Ok(v) => v,
+            Err(e) => {
+                println!("Failed to read the playlists! Error: {}", e);
+                process::exit(1);
+            }
+        };
+
+        let mut tag_counts = BTreeMap::<String, usize>::new();
+
+        for name in names {
+            let playlist_info = match PlaylistInfo::load(&name) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+
+            for tag in playlist_info.songs.iter().flat_map(|song| &song.tags) {
+                *tag_counts.entry(normalize_tag(tag)).or_default() += 1;
+            }
+        }
+
+        if tag_counts.is_empty() {
+            println!("No song has been tagged yet!");
+            return;
+        }
+
+        for (tag, count) in tag_counts {
+            println!("{} ({} songs)", tag, count);
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    fmt::Display,
+    path::Path,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context};
+use hound::WavReader;
+use serde::{Deserialize, Serialize};
+use soloud::{AudioExt, Handle, LoadExt, Soloud, Wav};
+
+/// Where the music is played. The player plays one song at a time, so the
+/// output keeps track of the song that is currently playing by itself
+pub trait AudioOutput: Send {
+    fn play(&mut self, wav: &Wav);
+
+    /// whether the song has not ended yet. A paused song is still playing
+    fn is_playing(&self) -> bool;
+
+    fn set_volume(&mut self, volume: f32);
+
+    fn set_pause(&mut self, pause: bool);
+
+    /// stop the current song
+    fn stop(&mut self);
+
+    /// move the current song to the position
+    fn seek(&mut self, position: Duration);
+
+    /// the position in the current song
+    fn position(&self) -> Duration;
+
+    /// the fft data of the mix, used by the visualizer
+    fn calc_fft(&self) -> Vec<f32>;
+
+    /// the wave data of the mix, used by the visualizer
+    fn wave(&self) -> Vec<f32>;
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AudioBackend {
+    /// Play through the sound card
+    #[default]
+    Soloud,
+
+    /// Play nothing. Songs still take their length to finish, which makes it
+    /// possible to run the player without a sound card
+    Null,
+}
+
+impl AudioBackend {
+    pub fn create_output(self) -> anyhow::Result<Box<dyn AudioOutput>> {
+        match self {
+            Self::Soloud => Ok(Box::new(SoloudOutput::new()?)),
+            Self::Null => Ok(Box::<NullOutput>::default()),
+        }
+    }
+}
+
+impl FromStr for AudioBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "soloud" => Ok(Self::Soloud),
+            "null" => Ok(Self::Null),
+            _ => Err(anyhow!(
+                "Unknown audio backend! Valid ones are: soloud, null"
+            )),
+        }
+    }
+}
+
+impl Display for AudioBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Soloud => write!(f, "Soloud"),
+            Self::Null => write!(f, "Null"),
+        }
+    }
+}
+
+/// the length of the audio file. Wav files are measured from their header,
+/// other files have to be decoded
+pub fn audio_length(path: &Path) -> Option<Duration> {
+    if let Ok(reader) = WavReader::open(path) {
+        let seconds = reader.duration() as f64 / reader.spec().sample_rate as f64;
+        return Some(Duration::from_secs_f64(seconds));
+    }
+
+    let mut wav = Wav::default();
+    wav.load(path).ok()?;
+
+    Some(Duration::from_secs_f64(wav.length().max(0.0)))
+}
+
+pub struct SoloudOutput {
+    sl: Soloud,
+    handle: Option<Handle>,
+}
+
+impl SoloudOutput {
+    pub fn new() -> anyhow::Result<Self> {
+        let sl = Soloud::default().with_context(|| "Failed to get player!")?;
+        sl.set_visualize_enable(true);
+
+        Ok(Self { sl, handle: None })
+    }
+}
+
+impl AudioOutput for SoloudOutput {
+    fn play(&mut self, wav: &Wav) {
+        self.handle = Some(self.sl.play(wav));
+    }
+
+    fn is_playing(&self) -> bool {
+        self.sl.voice_count() > 0
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        if let Some(handle) = self.handle {
+            self.sl.set_volume(handle, volume);
+        }
+    }
+
+    fn set_pause(&mut self, pause: bool) {
+        if let Some(handle) = self.handle {
+            self.sl.set_pause(handle, pause);
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.sl.stop(handle);
+        }
+    }
+
+    fn seek(&mut self, position: Duration) {
+        if let Some(handle) = self.handle {
+            // seeking fails if the song is shorter than the position, which
+            // makes the song end right away just like it should
+            let _ = self.sl.seek(handle, position.as_secs_f64());
+        }
+    }
+
+    fn position(&self) -> Duration {
+        self.handle
+            .map(|handle| Duration::from_secs_f64(self.sl.stream_position(handle).max(0.0)))
+            .unwrap_or_default()
+    }
+
+    fn calc_fft(&self) -> Vec<f32> {
+        self.sl.calc_fft()
+    }
+
+    fn wave(&self) -> Vec<f32> {
+        self.sl.wave()
+    }
+}
+
+/// An output that does not make any sound. It remembers what it is told so
+/// tests can check it
+#[derive(Debug, Default)]
+pub struct NullOutput {
+    pub volume: f32,
+    pub paused: bool,
+    pub length: Duration,
+    played: Duration,
+    resumed_at: Option<Instant>,
+}
+
+impl NullOutput {
+    /// jump to the end of the song
+    #[cfg(test)]
+    pub fn finish(&mut self) {
+        self.played = self.length;
+        self.resumed_at = None;
+    }
+}
+
+impl AudioOutput for NullOutput {
+    fn play(&mut self, wav: &Wav) {
+        self.length = Duration::from_secs_f64(wav.length().max(0.0));
+        self.played = Duration::ZERO;
+        self.paused = false;
+        self.resumed_at = Some(Instant::now());
+    }
+
+    fn is_playing(&self) -> bool {
+        self.position() < self.length
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+
+    fn set_pause(&mut self, pause: bool) {
+        if pause && !self.paused {
+            self.played = self.position();
+            self.resumed_at = None;
+        } else if !pause && self.paused {
+            self.resumed_at = Some(Instant::now());
+        }
+
+        self.paused = pause;
+    }
+
+    fn stop(&mut self) {
+        self.length = Duration::ZERO;
+        self.played = Duration::ZERO;
+        self.resumed_at = None;
+    }
+
+    fn seek(&mut self, position: Duration) {
+        self.played = position;
+
+        if self.resumed_at.is_some() {
+            self.resumed_at = Some(Instant::now());
+        }
+    }
+
+    /// how long the current song has been playing, not counting the time
+    /// it was paused
+    fn position(&self) -> Duration {
+        self.played + self.resumed_at.map(|i| i.elapsed()).unwrap_or_default()
+    }
+
+    fn calc_fft(&self) -> Vec<f32> {
+        vec![0.0; 256]
+    }
+
+    fn wave(&self) -> Vec<f32> {
+        vec![0.0; 256]
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::{thread, time::Duration};
+
+    use soloud::{AudioExt, LoadExt, Wav};
+
+    use super::{AudioOutput, NullOutput};
+
+    /// a silent mono 16 bit wav file of the given length
+    pub fn silent_wav_bytes(seconds: f32) -> Vec<u8> {
+        const SAMPLE_RATE: u32 = 8000;
+
+        let data_len = (SAMPLE_RATE as f32 * seconds) as u32 * 2;
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        bytes.resize(bytes.len() + data_len as usize, 0);
+
+        bytes
+    }
+
+    pub fn silent_wav(seconds: f32) -> Wav {
+        let mut wav = Wav::default();
+        wav.load_mem(&silent_wav_bytes(seconds)).unwrap();
+
+        wav
+    }
+
+    #[test]
+    fn null_output_finishes() {
+        let mut output = NullOutput::default();
+        output.play(&silent_wav(0.05));
+
+        assert!(output.is_playing());
+        thread::sleep(Duration::from_millis(80));
+        assert!(!output.is_playing());
+    }
+
+    #[test]
+    fn null_output_pause() {
+        let mut output = NullOutput::default();
+        output.play(&silent_wav(0.05));
+        output.set_pause(true);
+
+        thread::sleep(Duration::from_millis(80));
+        assert!(output.is_playing());
+
+        output.set_pause(false);
+        output.finish();
+        assert!(!output.is_playing());
+    }
+
+    #[test]
+    fn null_output_seek() {
+        let mut output = NullOutput::default();
+        output.play(&silent_wav(1.0));
+        output.set_pause(true);
+        output.seek(Duration::from_millis(400));
+
+        assert_eq!(output.position(), Duration::from_millis(400));
+
+        output.seek(Duration::from_secs(2));
+        assert!(!output.is_playing());
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+
+pub const BACKUPS_FOLDER: &str = "backups";
+
+/// write the file in a way that it is never left half written. The contents
+/// are written into a temporary file first, which then replaces the file
+pub fn write_atomic(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+
+        fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result.with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// the path of the nth newest backup of the file, starting from 1. Backups are
+/// kept in a folder next to the file
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+
+    path.with_file_name(BACKUPS_FOLDER)
+        .join(format!("{}.{}.{}", stem, n, extension))
+}
+
+/// back up the file before it is replaced with the new contents. The oldest
+/// backup is dropped once there are more than `count`. Nothing is backed up if
+/// the file does not exist or the contents do not change
+pub fn rotate_backups(path: &Path, new_contents: &[u8], count: usize) -> anyhow::Result<()> {
+    if count == 0 {
+        return Ok(());
+    }
+
+    let old_contents = match fs::read(path) {
+        Ok(v) => v,
+        Err(_) => return Ok(()),
+    };
+
+    if old_contents == new_contents {
+        return Ok(());
+    }
+
+    fs::create_dir_all(path.with_file_name(BACKUPS_FOLDER))?;
+
+    for n in (1..count).rev() {
+        let from = backup_path(path, n);
+
+        if from.is_file() {
+            fs::rename(&from, backup_path(path, n + 1))?;
+        }
+    }
+
+    write_atomic(&backup_path(path, 1), &old_contents)
+}
+
+/// the backups of the file from the newest to the oldest
+pub fn list_backups(path: &Path) -> Vec<(usize, PathBuf)> {
+    (1..)
+        .map(|n| (n, backup_path(path, n)))
+        .take_while(|(_, path)| path.is_file())
+        .collect()
+}
+
+/// read the nth newest backup of the file
+pub fn read_backup(path: &Path, n: usize) -> anyhow::Result<Vec<u8>> {
+    let backup = backup_path(path, n);
+
+    if !backup.is_file() {
+        return Err(anyhow!("There is no backup number {}", n));
+    }
+
+    Ok(fs::read(backup)?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+    };
+
+    use super::{list_backups, read_backup, rotate_backups, write_atomic};
+
+    fn temp_file(name: &str) -> PathBuf {
+        let folder = env::temp_dir().join(format!("music-player-backup-{}", name));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+
+        folder.join("info.json")
+    }
+
+    fn save(path: &Path, contents: &str) {
+        rotate_backups(path, contents.as_bytes(), 2).unwrap();
+        write_atomic(path, contents.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn backups_rotate() {
+        let path = temp_file("rotate");
+
+        save(&path, "1");
+        save(&path, "2");
+        save(&path, "2");
+        save(&path, "3");
+        save(&path, "4");
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "4");
+        assert_eq!(list_backups(&path).len(), 2);
+        assert_eq!(read_backup(&path, 1).unwrap(), b"3");
+        assert_eq!(read_backup(&path, 2).unwrap(), b"2");
+        assert!(read_backup(&path, 3).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context};
+use chrono::Local;
+use tar::{Archive, Builder, Header};
+
+use crate::cli::data::{PlaylistInfo, Song};
+
+use super::{
+    library,
+    migration::{self, Versioned},
+    playlist_names,
+    yt_downloader::downloads_folder,
+};
+
+const INFO_FILE: &str = "info.json";
+const AUDIO_FOLDER: &str = "audio";
+const LYRICS_FOLDER: &str = "lyrics";
+
+/// pack the playlist and every file its songs use into a tar file. Everything
+/// is put in a folder named after the playlist, and the songs point to the
+/// files inside it. Returns the songs whose files can't be found, they are
+/// packed with their old paths
+pub fn export(playlist_info: &PlaylistInfo, output: &Path) -> anyhow::Result<Vec<Song>> {
+    let mut files = BundleFiles::default();
+    let mut missing = Vec::new();
+    let mut songs = playlist_info.songs.clone();
+
+    for song in songs.iter_mut() {
+        let path = song.path();
+
+        if !path.exists() {
+            missing.push(song.clone());
+            continue;
+        }
+
+        song.path_to_song = PathBuf::from(files.add(AUDIO_FOLDER, &path));
+
+        if let Some(lyrics_path) = &song.lyrics_path {
+            let lyrics_path = library::resolve(lyrics_path);
+
+            if lyrics_path.exists() {
+                song.lyrics_path = Some(PathBuf::from(files.add(LYRICS_FOLDER, &lyrics_path)));
+            }
+        }
+    }
+
+    let info = serde_json::to_vec_pretty(&Versioned {
+        version: migration::PLAYLIST_VERSION,
+        data: &PlaylistInfo {
+            songs,
+            ..playlist_info.clone()
+        },
+    })?;
+
+    let file = File::create(output)?;
+    let mut builder = Builder::new(file);
+
+    // the info goes first, so importing knows the name before unpacking
+    // anything
+    let mut header = Header::new_gnu();
+    header.set_size(info.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Local::now().timestamp() as u64);
+    header.set_cksum();
+    builder.append_data(
+        &mut header,
+        format!("{}/{}", playlist_info.name, INFO_FILE),
+        info.as_slice(),
+    )?;
+
+    for (path, name) in files.by_path {
+        builder
+            .append_path_with_name(&path, format!("{}/{}", playlist_info.name, name))
+            .with_context(|| format!("Failed to pack {}", path.display()))?;
+    }
+
+    builder.into_inner()?.sync_all()?;
+
+    Ok(missing)
+}
+
+/// unpack a bundle made by `export` into a new playlist. The files are put
+/// into the downloads folder. Files that are already there with the same
+/// contents are used as they are. The name of the folder in the bundle is
+/// used as the name of the playlist if `name` is not given
+pub fn import(bundle: &Path, name: Option<&str>) -> anyhow::Result<PlaylistInfo> {
+    let mut archive = Archive::new(File::open(bundle)?);
+    let downloads = downloads_folder();
+
+    let mut playlist_info = None;
+    let mut unpacked = HashMap::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_path_buf();
+
+        let components = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(part) => Some(part),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let (folder, inside) = match components.split_first() {
+            Some((folder, inside)) if !inside.is_empty() => (
+                folder.to_string_lossy().to_string(),
+                inside.iter().collect::<PathBuf>(),
+            ),
+            _ => continue,
+        };
+
+        if inside == Path::new(INFO_FILE) {
+            let name = name.map(|name| name.to_string()).unwrap_or(folder);
+
+            if playlist_names().unwrap_or_default().contains(&name) {
+                bail!(r#"Playlist "{}" already exists!"#, name);
+            }
+
+            let mut data = String::new();
+            entry.read_to_string(&mut data)?;
+
+            let json = migration::migrate_playlist(serde_json::from_str(&data)?)?;
+            let stored: PlaylistInfo = serde_json::from_value(json)?;
+
+            // the ids are from the library of whoever made the bundle
+            let songs = stored
+                .songs
+                .into_iter()
+                .map(|song| Song {
+                    id: String::new(),
+                    ..song
+                })
+                .collect();
+
+            playlist_info = Some(PlaylistInfo {
+                songs,
+                created: stored.created,
+                ..PlaylistInfo::new(&name)
+            });
+            continue;
+        }
+
+        if playlist_info.is_none() {
+            bail!("{} is not a playlist bundle!", bundle.display());
+        }
+
+        // only the file name is used, so nothing can be written outside of
+        // the downloads folder
+        let file_name = match inside.file_name() {
+            Some(v) => v,
+            None => continue,
+        };
+
+        // the file is written next to where it goes, so audio files are never
+        // kept in memory
+        fs::create_dir_all(&downloads)?;
+        let part = downloads.join(format!(".{}.part", file_name.to_string_lossy()));
+        io::copy(&mut entry, &mut File::create(&part)?)
+            .with_context(|| format!("Failed to write {}", part.display()))?;
+
+        let target = free_path(&downloads.join(file_name), &part);
+
+        if target.exists() {
+            fs::remove_file(&part)?;
+        } else {
+            fs::rename(&part, &target)?;
+        }
+
+        unpacked.insert(bundle_key(&inside), target);
+    }
+
+    let mut playlist_info =
+        playlist_info.ok_or_else(|| anyhow!("{} is not a playlist bundle!", bundle.display()))?;
+
+    for song in playlist_info.songs.iter_mut() {
+        if let Some(path) = unpacked.get(&bundle_key(&song.path_to_song)) {
+            song.path_to_song = library::to_stored(path);
+        }
+
+        if let Some(path) = song
+            .lyrics_path
+            .as_ref()
+            .and_then(|p| unpacked.get(&bundle_key(p)))
+        {
+            song.lyrics_path = Some(library::to_stored(path));
+        }
+    }
+
+    Ok(playlist_info)
+}
+
+/// where a file is inside the bundle, with `/` between the folders. Bundles
+/// made on windows before the paths were written like that have `\\`, so
+/// both are accepted
+fn bundle_key(path: &Path) -> String {
+    path.to_string_lossy()
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// the path to move the unpacked file to. It is the path itself if there is
+/// no file there or if the file has the same contents, otherwise a number is
+/// put after the file name
+fn free_path(path: &Path, unpacked: &Path) -> PathBuf {
+    let mut candidate = path.to_path_buf();
+    let mut n = 1;
+
+    while candidate.exists() && !same_contents(&candidate, unpacked) {
+        candidate = numbered(path, n);
+        n += 1;
+    }
+
+    candidate
+}
+
+/// compares the files a piece at a time
+fn same_contents(a: &Path, b: &Path) -> bool {
+    let (Ok(a), Ok(b)) = (File::open(a), File::open(b)) else {
+        return false;
+    };
+
+    let len = |file: &File| file.metadata().map(|metadata| metadata.len()).ok();
+    if len(&a).is_none() || len(&a) != len(&b) {
+        return false;
+    }
+
+    let (mut a, mut b) = (BufReader::new(a), BufReader::new(b));
+    let (mut a_buf, mut b_buf) = ([0; 8192], [0; 8192]);
+
+    loop {
+        let n = match a.read(&mut a_buf) {
+            Ok(0) => return true,
+            Ok(n) => n,
+            Err(_) => return false,
+        };
+
+        if b.read_exact(&mut b_buf[..n]).is_err() || a_buf[..n] != b_buf[..n] {
+            return false;
+        }
+    }
+}
+
+/// `song.wav` becomes `song-<n>.wav`
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    path.with_file_name(format!("{}-{}{}", stem, n, extension))
+}
+
+/// The files to pack and where they go inside the bundle. Songs that use the
+/// same file share it, and files with the same name get a number after it
+#[derive(Default)]
+struct BundleFiles {
+    by_path: BTreeMap<PathBuf, String>,
+    names: HashSet<String>,
+}
+
+impl BundleFiles {
+    /// returns where the file is inside the bundle. It always has `/` between
+    /// the folders, so bundles can be imported on every system
+    fn add(&mut self, folder: &str, path: &Path) -> String {
+        if let Some(name) = self.by_path.get(path) {
+            return name.clone();
+        }
+
+        let file_name = Path::new(path.file_name().unwrap_or_default());
+        let mut name = format!("{}/{}", folder, file_name.to_string_lossy());
+        let mut n = 1;
+
+        while self.names.contains(&name) {
+            name = format!("{}/{}", folder, numbered(file_name, n).to_string_lossy());
+            n += 1;
+        }
+
+        self.names.insert(name.clone());
+        self.by_path.insert(path.to_path_buf(), name.clone());
+
+        name
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{bundle_key, BundleFiles};
+
+    #[test]
+    fn bundle_file_names() {
+        let mut files = BundleFiles::default();
+
+        assert_eq!(
+            files.add("audio", Path::new("/music/song.wav")),
+            "audio/song.wav"
+        );
+        assert_eq!(
+            files.add("audio", Path::new("/other/song.wav")),
+            "audio/song-1.wav"
+        );
+        // chapters of the same file share it
+        assert_eq!(
+            files.add("audio", Path::new("/music/song.wav")),
+            "audio/song.wav"
+        );
+        assert_eq!(files.by_path.len(), 2);
+    }
+
+    #[test]
+    fn bundle_keys() {
+        assert_eq!(bundle_key(Path::new("audio/song.wav")), "audio/song.wav");
+        // made on windows
+        assert_eq!(bundle_key(Path::new("audio\\song.wav")), "audio/song.wav");
+        assert_eq!(bundle_key(Path::new("./audio//song.wav")), "audio/song.wav");
+    }
+}
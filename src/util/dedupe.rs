@@ -0,0 +1,251 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    fs::File,
+    hash::Hasher,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use crate::cli::data::Song;
+
+use super::yt_downloader::video_id;
+
+/// What makes two songs the same song. Every key also has the part of the
+/// file that is played, so chapters of the same file are not duplicates
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Path(PathBuf, String),
+    VideoId(String, String),
+    Content(u64, String),
+}
+
+/// find the songs that are the same song, either the same file, the same
+/// downloaded video or a file with the same contents. Every group is sorted
+/// by index, and only groups with more than one song are returned
+pub fn find_duplicates(songs: &[Song]) -> Vec<Vec<usize>> {
+    let mut groups = Groups::new(songs.len());
+    let mut first_with_key = HashMap::new();
+    let mut hashes = HashMap::new();
+
+    for (index, song) in songs.iter().enumerate() {
+        let (path, part) = path_key(song);
+
+        let content = hashes
+            .entry(path.clone())
+            .or_insert_with(|| content_hash(&path))
+            .map(|hash| Key::Content(hash, part.clone()));
+
+        let keys = [
+            Some(Key::Path(path.clone(), part.clone())),
+            video_id(&path).map(|id| Key::VideoId(id, part.clone())),
+            content,
+        ];
+
+        for key in keys.into_iter().flatten() {
+            let first = *first_with_key.entry(key).or_insert(index);
+            groups.join(first, index);
+        }
+    }
+
+    let mut by_root = HashMap::<usize, Vec<usize>>::new();
+
+    for index in 0..songs.len() {
+        by_root.entry(groups.root(index)).or_default().push(index);
+    }
+
+    let mut duplicates = by_root
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect::<Vec<_>>();
+    duplicates.sort_unstable();
+
+    duplicates
+}
+
+/// the file of the song and the part of it that is played. Songs with the
+/// same key play the same thing
+pub fn path_key(song: &Song) -> (PathBuf, String) {
+    (
+        song.path(),
+        format!("{:?}-{:?}", song.start_at, song.end_at),
+    )
+}
+
+/// keep what was recorded and customized in the duplicates that are removed.
+/// The counts are added up and the tags joined like `Song::merge` does, the
+/// highest sound multiplier is kept, and a name that was given by hand is
+/// kept over the name of the file. A song of the library that is in the
+/// playlist more than once is only counted once
+pub fn merge_duplicates(kept: &mut Song, duplicates: &[&Song]) {
+    let mut merged_ids = HashSet::from([kept.id.clone()]);
+
+    for duplicate in duplicates {
+        if duplicate.id.is_empty() || merged_ids.insert(duplicate.id.clone()) {
+            kept.merge(duplicate);
+        }
+
+        kept.sound_multiplier = kept.sound_multiplier.max(duplicate.sound_multiplier);
+
+        if !has_custom_name(kept) && has_custom_name(duplicate) {
+            kept.song_name = duplicate.song_name.clone();
+        }
+    }
+}
+
+/// songs added from a file are named after the file
+fn has_custom_name(song: &Song) -> bool {
+    let path = song.path();
+    let is_file_name = |name: Option<&std::ffi::OsStr>| {
+        name.map(|name| name.to_string_lossy() == song.song_name)
+            .unwrap_or_default()
+    };
+
+    !is_file_name(path.file_name()) && !is_file_name(path.file_stem())
+}
+
+/// the file is read a piece at a time, so big audio files are never kept in
+/// memory
+fn content_hash(path: &Path) -> Option<u64> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let mut hasher = DefaultHasher::new();
+
+    loop {
+        let data = reader.fill_buf().ok()?;
+
+        if data.is_empty() {
+            break;
+        }
+
+        hasher.write(data);
+        let len = data.len();
+        reader.consume(len);
+    }
+
+    Some(hasher.finish())
+}
+
+/// songs that are joined end up with the same root
+struct Groups {
+    parents: Vec<usize>,
+}
+
+impl Groups {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn root(&mut self, mut index: usize) -> usize {
+        while self.parents[index] != index {
+            self.parents[index] = self.parents[self.parents[index]];
+            index = self.parents[index];
+        }
+
+        index
+    }
+
+    fn join(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.root(a), self.root(b));
+
+        // the song that comes first stays the root
+        self.parents[a.max(b)] = a.min(b);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::cli::data::{default_sound_multiplier, Song};
+
+    use super::{content_hash, find_duplicates, merge_duplicates};
+
+    fn song(name: &str, path: &str) -> Song {
+        Song::new(
+            name.to_string(),
+            PathBuf::from(path),
+            None,
+            default_sound_multiplier(),
+        )
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut chapter = song("chapter", "/missing/album.wav");
+        chapter.start_at = Some(60.0);
+
+        let songs = [
+            song("a", "/missing/a.wav"),
+            song("album", "/missing/album.wav"),
+            song(
+                "video",
+                "/home/rust-cli-music-player_downloaded-audios/abc.mp3",
+            ),
+            song("a again", "/missing/a.wav"),
+            chapter,
+            song(
+                "video",
+                "/home/rust-cli-music-player_downloaded-audios/abc.m4a",
+            ),
+        ];
+
+        assert_eq!(find_duplicates(&songs), vec![vec![0, 3], vec![2, 5]]);
+    }
+
+    #[test]
+    fn customizations_are_kept() {
+        let mut kept = song("a.wav", "/music/a.wav");
+        let mut duplicate = song("My Song", "/music/a.wav");
+        duplicate.sound_multiplier = 1.5;
+
+        merge_duplicates(&mut kept, &[&duplicate]);
+
+        assert_eq!(kept.song_name, "My Song");
+        assert_eq!(kept.sound_multiplier, 1.5);
+    }
+
+    #[test]
+    fn stats_are_merged() {
+        let mut kept = song("a", "/music/a.wav");
+        kept.id = "1".to_string();
+        kept.play_count = 2;
+        kept.tags = vec!["rock".to_string()];
+
+        let mut duplicate = song("a", "/music/a.wav");
+        duplicate.id = "2".to_string();
+        duplicate.play_count = 3;
+        duplicate.skip_count = 1;
+        duplicate.rating = Some(4);
+        duplicate.favorite = true;
+        duplicate.tags = vec!["live".to_string()];
+
+        // the same song of the library again, its counts are already there
+        let same_song = kept.clone();
+
+        merge_duplicates(&mut kept, &[&duplicate, &same_song]);
+
+        assert_eq!(kept.play_count, 5);
+        assert_eq!(kept.skip_count, 1);
+        assert_eq!(kept.rating, Some(4));
+        assert!(kept.favorite);
+        assert_eq!(kept.tags, ["rock", "live"]);
+    }
+
+    #[test]
+    fn same_contents_have_the_same_hash() {
+        let folder = std::path::Path::new(&crate::util::home_folder()).join("content_hash");
+        std::fs::create_dir_all(&folder).unwrap();
+
+        let data = (0..20_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        std::fs::write(folder.join("a.wav"), &data).unwrap();
+        std::fs::write(folder.join("b.wav"), &data).unwrap();
+        std::fs::write(folder.join("c.wav"), &data[1..]).unwrap();
+
+        let hash = |name: &str| content_hash(&folder.join(name));
+        assert!(hash("a.wav").is_some());
+        assert_eq!(hash("a.wav"), hash("b.wav"));
+        assert_ne!(hash("a.wav"), hash("c.wav"));
+        assert_eq!(hash("missing.wav"), None);
+    }
+}
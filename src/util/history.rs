@@ -0,0 +1,170 @@
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+use anyhow::anyhow;
+use basic_quick_lib::time::LocalTime;
+use serde::{Deserialize, Serialize};
+
+use super::{home_folder, storage::storage};
+
+const HISTORY_FILE: &str = "rust-cli-music-player_history.jsonl";
+
+/// A single entry of the listening history. One is recorded every time the
+/// player moves away from a song, either because it finished or because it
+/// got skipped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayEvent {
+    pub song_name: String,
+
+    #[serde(default)]
+    pub author: Option<String>,
+
+    #[serde(default)]
+    pub path_to_song: PathBuf,
+
+    pub playlist: String,
+
+    pub started: LocalTime,
+
+    pub seconds_listened: u64,
+
+    pub skipped: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow!("Unknown export format! Valid ones are: csv, json")),
+        }
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Csv => write!(f, "csv"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+pub fn history_path() -> PathBuf {
+    let mut path = PathBuf::from(home_folder());
+    path.push(HISTORY_FILE);
+
+    path
+}
+
+/// add the event to the end of the history
+pub fn append(event: &PlayEvent) -> anyhow::Result<()> {
+    storage().append_history(event)
+}
+
+pub fn read_history() -> anyhow::Result<Vec<PlayEvent>> {
+    storage().read_history()
+}
+
+/// parse the content of the history log of the json storage. Lines that
+/// cannot be parsed (for example a half written line) are ignored
+pub fn parse_history(data: &str) -> Vec<PlayEvent> {
+    data.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+pub fn to_csv(events: &[PlayEvent]) -> String {
+    let mut csv =
+        String::from("started,playlist,song_name,author,path_to_song,seconds_listened,skipped\n");
+
+    for event in events {
+        let fields = [
+            event.started.to_rfc3339(),
+            event.playlist.clone(),
+            event.song_name.clone(),
+            event.author.clone().unwrap_or_default(),
+            event.path_to_song.to_string_lossy().to_string(),
+            event.seconds_listened.to_string(),
+            event.skipped.to_string(),
+        ];
+
+        let line = fields
+            .iter()
+            .map(|field| escape_csv(field))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        csv.push_str(&line);
+        csv.push('\n');
+    }
+
+    csv
+}
+
+pub fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use basic_quick_lib::time::LocalTime;
+    use chrono::Local;
+
+    use super::{escape_csv, parse_history, to_csv, PlayEvent};
+
+    fn event(song_name: &str) -> PlayEvent {
+        PlayEvent {
+            song_name: song_name.to_string(),
+            author: Some("Lucas Fan".to_string()),
+            path_to_song: PathBuf::from("song.wav"),
+            playlist: "test".to_string(),
+            started: LocalTime(Local::now()),
+            seconds_listened: 42,
+            skipped: true,
+        }
+    }
+
+    #[test]
+    fn parse_skips_broken_lines() {
+        let data = format!(
+            "{}\n{{\"song_name\": \"half writ\n\n{}\n",
+            serde_json::to_string(&event("a")).unwrap(),
+            serde_json::to_string(&event("b")).unwrap()
+        );
+
+        let events = parse_history(&data);
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].song_name, "a");
+        assert_eq!(events[1].song_name, "b");
+    }
+
+    #[test]
+    fn csv_escaping() {
+        assert_eq!(escape_csv("plain"), "plain");
+        assert_eq!(escape_csv("a, b"), "\"a, b\"");
+        assert_eq!(escape_csv("say \"hi\""), "\"say \"\"hi\"\"\"");
+
+        let csv = to_csv(&[event("one, two")]);
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains("\"one, two\""));
+        assert!(lines[1].ends_with(",42,true"));
+    }
+}
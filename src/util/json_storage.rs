@@ -0,0 +1,289 @@
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cli::data::Song;
+
+use super::{
+    backup,
+    history::{self, PlayEvent},
+    lock::FileLock,
+    migration::{self, Versioned, LIBRARY_VERSION},
+    playlist_info_folder, playlist_info_path, playlists_folder,
+    settings::{Settings, SETTINGS},
+    storage::{Storage, StorageKind},
+};
+
+pub const LIBRARY_FILE: &str = "library.json";
+
+/// Every playlist is a folder with an `info.json` under the playlists folder.
+/// The song library is `library.json` next to them. The listening history is
+/// a json object per line, so a crash can at most lose the line that is being
+/// written
+pub struct JsonStorage;
+
+#[derive(Default, Serialize, Deserialize)]
+struct LibraryFile {
+    #[serde(default)]
+    songs: Vec<Song>,
+}
+
+impl JsonStorage {
+    pub fn library_path() -> PathBuf {
+        playlists_folder().join(LIBRARY_FILE)
+    }
+}
+
+impl Storage for JsonStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Json
+    }
+
+    fn playlist_names(&self) -> anyhow::Result<Vec<String>> {
+        let mut names = Vec::new();
+
+        for entry in fs::read_dir(playlists_folder())? {
+            let entry = entry?;
+
+            if entry.path().join("info.json").is_file() {
+                names.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+
+        names.sort();
+
+        Ok(names)
+    }
+
+    fn read_playlist(&self, name: &str) -> anyhow::Result<Value> {
+        let data = fs::read_to_string(playlist_info_path(name))?;
+
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// the old `info.json` is kept as a backup
+    fn write_playlist(&self, name: &str, playlist: &Value) -> anyhow::Result<()> {
+        fs::create_dir_all(playlist_info_folder(name))?;
+
+        let path = playlist_info_path(name);
+        let contents = serde_json::to_string_pretty(playlist)?;
+
+        backup::rotate_backups(&path, contents.as_bytes(), SETTINGS.read().backup_count)?;
+        backup::write_atomic(&path, contents.as_bytes())
+    }
+
+    fn delete_playlist(&self, name: &str) -> anyhow::Result<()> {
+        Ok(fs::remove_dir_all(playlist_info_folder(name))?)
+    }
+
+    fn rename_playlist(&self, name: &str, new_name: &str) -> anyhow::Result<()> {
+        let new_path = playlist_info_folder(new_name);
+
+        if new_path.exists() {
+            bail!(r#"Playlist "{}" already exists!"#, new_name);
+        }
+
+        Ok(fs::rename(playlist_info_folder(name), new_path)?)
+    }
+
+    fn read_songs(&self) -> anyhow::Result<Vec<Song>> {
+        let path = Self::library_path();
+
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let data = fs::read_to_string(&path)?;
+        let json = migration::migrate_library(serde_json::from_str(&data)?)?;
+
+        Ok(serde_json::from_value::<LibraryFile>(json)?.songs)
+    }
+
+    fn write_songs(&self, songs: &[Song]) -> anyhow::Result<()> {
+        let path = Self::library_path();
+        fs::create_dir_all(playlists_folder())?;
+
+        let _lock = FileLock::exclusive(&path)?;
+
+        let mut library = LibraryFile {
+            songs: self.read_songs()?,
+        };
+        let mut index = library
+            .songs
+            .iter()
+            .enumerate()
+            .map(|(i, song)| (song.id.clone(), i))
+            .collect::<HashMap<_, _>>();
+
+        for song in songs {
+            match index.get(&song.id) {
+                Some(&i) => library.songs[i] = song.clone(),
+                None => {
+                    index.insert(song.id.clone(), library.songs.len());
+                    library.songs.push(song.clone());
+                }
+            }
+        }
+
+        let json_string = serde_json::to_string_pretty(&Versioned {
+            version: LIBRARY_VERSION,
+            data: &library,
+        })?;
+
+        backup::rotate_backups(&path, json_string.as_bytes(), SETTINGS.read().backup_count)?;
+        backup::write_atomic(&path, json_string.as_bytes())
+    }
+
+    fn read_settings(&self) -> anyhow::Result<Option<Value>> {
+        let path = PathBuf::from(Settings::path());
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    fn write_settings(&self, settings: &Value) -> anyhow::Result<()> {
+        let contents = serde_json::to_string_pretty(settings)?;
+
+        backup::write_atomic(&PathBuf::from(Settings::path()), contents.as_bytes())
+    }
+
+    fn append_history(&self, event: &PlayEvent) -> anyhow::Result<()> {
+        let line = serde_json::to_string(event)?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(history::history_path())?;
+        writeln!(file, "{}", line)?;
+
+        Ok(())
+    }
+
+    fn read_history(&self) -> anyhow::Result<Vec<PlayEvent>> {
+        match fs::read_to_string(history::history_path()) {
+            Ok(data) => Ok(history::parse_history(&data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn write_history(&self, events: &[PlayEvent]) -> anyhow::Result<()> {
+        let mut contents = String::new();
+
+        for event in events {
+            contents.push_str(&serde_json::to_string(event)?);
+            contents.push('\n');
+        }
+
+        backup::write_atomic(&history::history_path(), contents.as_bytes())
+    }
+
+    fn lock_playlist(&self, name: &str) -> anyhow::Result<FileLock> {
+        fs::create_dir_all(playlist_info_folder(name))?;
+
+        FileLock::exclusive(&playlist_info_path(name))
+    }
+
+    fn lock_settings(&self) -> anyhow::Result<FileLock> {
+        FileLock::exclusive(&PathBuf::from(Settings::path()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use basic_quick_lib::time::LocalTime;
+    use chrono::Local;
+    use serde_json::json;
+
+    use crate::{
+        cli::data::{default_sound_multiplier, Song},
+        util::{history::PlayEvent, storage::Storage},
+    };
+
+    use super::JsonStorage;
+
+    #[test]
+    fn playlists() -> anyhow::Result<()> {
+        let playlist = json!({ "version": 3, "songs": ["1"], "created": null });
+
+        JsonStorage.write_playlist("json-a", &playlist)?;
+        JsonStorage.write_playlist("json-c", &playlist)?;
+        assert_eq!(JsonStorage.read_playlist("json-a")?, playlist);
+
+        JsonStorage.rename_playlist("json-a", "json-b")?;
+        assert!(JsonStorage.rename_playlist("json-b", "json-c").is_err());
+
+        let names = JsonStorage.playlist_names()?;
+        assert!(names.contains(&"json-b".to_string()));
+        assert!(!names.contains(&"json-a".to_string()));
+
+        JsonStorage.delete_playlist("json-b")?;
+        assert!(JsonStorage.read_playlist("json-b").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn songs_are_replaced_by_id() -> anyhow::Result<()> {
+        let song = |id: &str, name: &str| Song {
+            id: id.to_string(),
+            ..Song::new(
+                name.to_string(),
+                PathBuf::from("song.wav"),
+                None,
+                default_sound_multiplier(),
+            )
+        };
+
+        JsonStorage.write_songs(&[song("json-1", "a"), song("json-2", "b")])?;
+        JsonStorage.write_songs(&[song("json-1", "renamed")])?;
+
+        let songs = JsonStorage.read_songs()?;
+        let names = songs
+            .iter()
+            .filter(|song| song.id.starts_with("json-"))
+            .map(|song| song.song_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["renamed", "b"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn history_is_appended() -> anyhow::Result<()> {
+        let event = PlayEvent {
+            song_name: "song".to_string(),
+            author: None,
+            path_to_song: PathBuf::from("song.wav"),
+            playlist: "json-history".to_string(),
+            started: LocalTime(Local::now()),
+            seconds_listened: 10,
+            skipped: true,
+        };
+
+        JsonStorage.append_history(&event)?;
+        JsonStorage.append_history(&event)?;
+
+        let appended = JsonStorage
+            .read_history()?
+            .into_iter()
+            .filter(|event| event.playlist == "json-history")
+            .count();
+        assert_eq!(appended, 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{home_folder, settings::SETTINGS};
+
+/// The folder song paths are stored relative to. Moving the whole library
+/// only needs the root to be changed. It is the home directory if it is not
+/// set in the settings
+pub fn root() -> PathBuf {
+    SETTINGS
+        .read()
+        .library_root
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(home_folder()))
+}
+
+/// the path to store for the file. Files inside the library root are stored
+/// relative to it, everything else is stored as an absolute path
+pub fn to_stored(path: &Path) -> PathBuf {
+    to_stored_from(path, &root())
+}
+
+/// both paths are made canonical before they are compared, so a root behind
+/// a symbolic link, or the `\\?\` prefix canonical paths have on windows,
+/// still matches
+fn to_stored_from(path: &Path, root: &Path) -> PathBuf {
+    relative_to(&canonical(path), &canonical(root))
+}
+
+/// the canonical path, or the absolute path if the file does not exist
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| match env::current_dir() {
+        Ok(dir) if path.is_relative() => dir.join(path),
+        _ => path.to_path_buf(),
+    })
+}
+
+/// the path of the file a stored path points to
+pub fn resolve(path: &Path) -> PathBuf {
+    resolve_from(path, &root())
+}
+
+pub fn relative_to(path: &Path, root: &Path) -> PathBuf {
+    match path.strip_prefix(root) {
+        Ok(relative) if path.is_absolute() => relative.to_path_buf(),
+        _ => path.to_path_buf(),
+    }
+}
+
+pub fn resolve_from(path: &Path, root: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        root.join(path)
+    }
+}
+
+/// replace the start of the path. Returns `None` if the path does not start
+/// with `from`
+pub fn replace_prefix(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    path.strip_prefix(from).ok().map(|rest| to.join(rest))
+}
+
+/// every file under the folder, grouped by file name. Folders that are linked
+/// to more than once are only searched once, so links in a loop end
+pub fn files_by_name(folder: &Path) -> HashMap<OsString, Vec<PathBuf>> {
+    let mut files: HashMap<_, Vec<_>> = HashMap::new();
+    let mut folders = vec![folder.to_path_buf()];
+    let mut visited = HashSet::new();
+
+    while let Some(folder) = folders.pop() {
+        if !visited.insert(canonical(&folder)) {
+            continue;
+        }
+
+        let entries = match fs::read_dir(&folder) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                folders.push(path);
+            } else {
+                files.entry(entry.file_name()).or_default().push(path);
+            }
+        }
+    }
+
+    files
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::{files_by_name, relative_to, replace_prefix, resolve_from, to_stored_from};
+
+    #[test]
+    fn relative_paths() {
+        let root = Path::new("/home/me/music");
+
+        assert_eq!(
+            relative_to(Path::new("/home/me/music/album/song.wav"), root),
+            PathBuf::from("album/song.wav")
+        );
+        assert_eq!(
+            relative_to(Path::new("/mnt/song.wav"), root),
+            PathBuf::from("/mnt/song.wav")
+        );
+        assert_eq!(
+            resolve_from(Path::new("album/song.wav"), Path::new("/music")),
+            PathBuf::from("/music/album/song.wav")
+        );
+        assert_eq!(
+            resolve_from(Path::new("/mnt/song.wav"), root),
+            PathBuf::from("/mnt/song.wav")
+        );
+    }
+
+    #[test]
+    fn prefix() {
+        assert_eq!(
+            replace_prefix(
+                Path::new("/old/music/song.wav"),
+                Path::new("/old/music"),
+                Path::new("/new")
+            ),
+            Some(PathBuf::from("/new/song.wav"))
+        );
+        assert_eq!(
+            replace_prefix(
+                Path::new("/other/song.wav"),
+                Path::new("/old"),
+                Path::new("/new")
+            ),
+            None
+        );
+    }
+
+    /// a folder of its own for the test
+    #[cfg(unix)]
+    fn test_folder(name: &str) -> PathBuf {
+        let folder = Path::new(&crate::util::home_folder()).join(name);
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+
+        folder
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn root_behind_a_link() {
+        let folder = test_folder("root_behind_a_link");
+        let music = folder.join("music");
+        std::fs::create_dir(&music).unwrap();
+        std::fs::write(music.join("song.wav"), []).unwrap();
+        std::os::unix::fs::symlink(&music, folder.join("link")).unwrap();
+
+        assert_eq!(
+            to_stored_from(&music.join("song.wav"), &folder.join("link")),
+            PathBuf::from("song.wav")
+        );
+        assert_eq!(
+            to_stored_from(&folder.join("link/song.wav"), &music),
+            PathBuf::from("song.wav")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_in_a_loop() {
+        let folder = test_folder("links_in_a_loop");
+        std::fs::create_dir(folder.join("album")).unwrap();
+        std::fs::write(folder.join("album/song.wav"), []).unwrap();
+        std::os::unix::fs::symlink(&folder, folder.join("album/back")).unwrap();
+
+        let files = files_by_name(&folder);
+        assert_eq!(files[std::ffi::OsStr::new("song.wav")].len(), 1);
+    }
+}
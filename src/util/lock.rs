@@ -0,0 +1,42 @@
+use std::{
+    fs::{File, OpenOptions},
+    path::Path,
+};
+
+use anyhow::Context;
+use fs2::FileExt;
+
+/// A lock that is shared by every process of the music player. It is held
+/// until it is dropped
+///
+/// The lock is taken on a `.lock` file next to the file, since saving replaces
+/// the file itself
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// wait until no other process holds the lock of the file, then take it
+    pub fn exclusive(path: &Path) -> anyhow::Result<Self> {
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open {}", Path::new(&lock_path).display()))?;
+
+        file.lock_exclusive()
+            .with_context(|| format!("Failed to lock {}", path.display()))?;
+
+        Ok(Self { file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
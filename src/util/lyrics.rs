@@ -0,0 +1,161 @@
+use std::{fs, path::Path, time::Duration};
+
+use crate::cli::data::Song;
+
+use super::library;
+
+/// Lyrics synchronized to the song, parsed from a `.lrc` file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lyrics {
+    /// Sorted by the time the line starts
+    pub lines: Vec<(Duration, String)>,
+}
+
+impl Lyrics {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// load the lyrics attached to the song, or the `.lrc` file next to the
+    /// song if there isn't one attached
+    pub fn for_song(song: &Song) -> Option<Self> {
+        let path = match &song.lyrics_path {
+            Some(path) => library::resolve(path),
+            None => song.path().with_extension("lrc"),
+        };
+
+        Self::load(&path)
+            .ok()
+            .filter(|lyrics| !lyrics.lines.is_empty())
+    }
+
+    /// parse the content of a `.lrc` file. Lines without a time tag and the
+    /// metadata tags (like `[ar:Author]`) are ignored
+    pub fn parse(data: &str) -> Self {
+        let mut offset_ms = 0_i64;
+        let mut lines = Vec::new();
+
+        for line in data.lines() {
+            let mut rest = line.trim();
+            let mut times = Vec::new();
+
+            while let Some(tag_end) = rest.strip_prefix('[').and_then(|r| r.find(']')) {
+                let tag = &rest[1..tag_end + 1];
+                rest = &rest[tag_end + 2..];
+
+                if let Some(offset) = tag.strip_prefix("offset:") {
+                    offset_ms = offset.trim().parse().unwrap_or(0);
+                } else if let Some(time) = parse_time(tag) {
+                    times.push(time);
+                }
+            }
+
+            for time in times {
+                lines.push((time, rest.trim().to_string()));
+            }
+        }
+
+        // a positive offset makes the lyrics show up sooner
+        let lines = lines
+            .into_iter()
+            .map(|(time, text)| {
+                let ms = (time.as_millis() as i64 - offset_ms).max(0);
+                (Duration::from_millis(ms as u64), text)
+            })
+            .collect::<Vec<_>>();
+
+        let mut this = Self { lines };
+        this.lines.sort_by_key(|(time, _)| *time);
+
+        this
+    }
+
+    /// the index of the line that is being sung at the position
+    pub fn current_index(&self, position: Duration) -> Option<usize> {
+        self.lines.iter().rposition(|(time, _)| *time <= position)
+    }
+
+    /// the previous, current and next line at the position
+    pub fn around(&self, position: Duration) -> [Option<&str>; 3] {
+        let text = |index: Option<usize>| {
+            index
+                .and_then(|i| self.lines.get(i))
+                .map(|(_, text)| text.as_str())
+        };
+
+        match self.current_index(position) {
+            Some(i) => [text(i.checked_sub(1)), text(Some(i)), text(Some(i + 1))],
+            None => [None, None, text(Some(0))],
+        }
+    }
+}
+
+/// parse `mm:ss`, `mm:ss.xx` or `mm:ss:xx`
+fn parse_time(tag: &str) -> Option<Duration> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let minutes: u64 = minutes.trim().parse().ok()?;
+    let seconds: f64 = seconds.trim().replacen(':', ".", 1).parse().ok()?;
+
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+
+    Some(Duration::from_secs(minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::Lyrics;
+
+    const LRC: &str = "[ar:Someone]
+[ti:Something]
+[00:01.00]first line
+[00:03.50][00:10.00]chorus
+[00:05.25]second line
+not a lyric line
+";
+
+    #[test]
+    fn parse() {
+        let lyrics = Lyrics::parse(LRC);
+
+        assert_eq!(lyrics.lines.len(), 4);
+        assert_eq!(
+            lyrics.lines[0],
+            (Duration::from_secs(1), "first line".to_string())
+        );
+        assert_eq!(lyrics.lines[1].1, "chorus");
+        assert_eq!(lyrics.lines[2].1, "second line");
+        assert_eq!(
+            lyrics.lines[3],
+            (Duration::from_secs(10), "chorus".to_string())
+        );
+    }
+
+    #[test]
+    fn offset() {
+        let lyrics = Lyrics::parse("[offset:500]\n[00:02.00]line");
+
+        assert_eq!(lyrics.lines[0].0, Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn around() {
+        let lyrics = Lyrics::parse(LRC);
+
+        assert_eq!(
+            lyrics.around(Duration::ZERO),
+            [None, None, Some("first line")]
+        );
+        assert_eq!(
+            lyrics.around(Duration::from_secs(4)),
+            [Some("first line"), Some("chorus"), Some("second line")]
+        );
+        assert_eq!(
+            lyrics.around(Duration::from_secs(60)),
+            [Some("second line"), Some("chorus"), None]
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{Map, Value};
+
+/// merge the changes made by another process into the data that is about to
+/// be saved. `base` is what was loaded from the file, `ours` is what is in
+/// memory and `disk` is what is in the file now. Whatever was changed in memory
+/// wins, everything else is taken from the file
+pub fn merge_objects(base: Option<&Value>, ours: &Value, disk: &Value) -> Value {
+    let mut merged = Map::new();
+
+    let keys = ours
+        .as_object()
+        .into_iter()
+        .chain(disk.as_object())
+        .flat_map(|object| object.keys());
+
+    for key in keys {
+        if merged.contains_key(key) {
+            continue;
+        }
+
+        let value = merge_field(
+            base.and_then(|base| base.get(key)),
+            ours.get(key),
+            disk.get(key),
+        );
+
+        if let Some(value) = value {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+
+    Value::Object(merged)
+}
+
+fn merge_field<'a>(
+    base: Option<&Value>,
+    ours: Option<&'a Value>,
+    disk: Option<&'a Value>,
+) -> Option<&'a Value> {
+    match ours {
+        Some(ours) if base != Some(ours) => Some(ours),
+        _ => disk.or(ours),
+    }
+}
+
+/// merge a playlist read from `info.json`. Songs added by another process are
+/// put after the songs in memory, and songs removed by another process are
+/// removed unless they were changed in memory. The songs keep the order in
+/// memory, unless only another process reordered them
+pub fn merge_playlist(base: Option<&Value>, ours: &Value, disk: &Value) -> Value {
+    let mut merged = merge_objects(base, ours, disk);
+
+    let songs = |playlist: Option<&Value>| {
+        playlist
+            .and_then(|playlist| playlist["songs"].as_array())
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    let songs = merge_songs(&songs(base), &songs(Some(ours)), &songs(Some(disk)));
+    merged["songs"] = Value::Array(songs);
+
+    merged
+}
+
+fn merge_songs(base: &[Value], ours: &[Value], disk: &[Value]) -> Vec<Value> {
+    let base_keyed = keyed(base);
+    let base = base_keyed.iter().cloned().collect::<HashMap<_, _>>();
+    let disk_keyed = keyed(disk);
+    let disk_songs = disk_keyed.iter().cloned().collect::<HashMap<_, _>>();
+    let ours = keyed(ours);
+    let our_keys = ours.iter().map(|(key, _)| key).collect::<HashSet<_>>();
+
+    let mut merged = Vec::new();
+
+    for (key, song) in &ours {
+        match (base.get(key), disk_songs.get(key)) {
+            // removed by another process, and not changed here
+            (Some(&base_song), None) if base_song == *song => {}
+            // changed by another process only
+            (Some(&base_song), Some(&disk_song)) if base_song == *song => {
+                merged.push((key, disk_song))
+            }
+            _ => merged.push((key, *song)),
+        }
+    }
+
+    for (key, song) in &disk_keyed {
+        // added by another process. Songs that were loaded but are not in
+        // memory anymore were removed here
+        if !our_keys.contains(key) && !base.contains_key(key) {
+            merged.push((key, *song));
+        }
+    }
+
+    // nothing that was loaded was reordered in memory, so the songs that are
+    // in the file are put in the order of the file. Songs that are not in the
+    // file stay where they are
+    let loaded = |key: &&Key| base.contains_key(*key) && our_keys.contains(*key);
+    let our_order = ours.iter().map(|(key, _)| key).filter(loaded);
+    let base_order = base_keyed.iter().map(|(key, _)| key).filter(loaded);
+
+    if !base.is_empty() && our_order.eq(base_order) {
+        let disk_order = disk_keyed
+            .iter()
+            .enumerate()
+            .map(|(index, (key, _))| (key, index))
+            .collect::<HashMap<_, _>>();
+
+        let slots = (0..merged.len())
+            .filter(|&index| disk_order.contains_key(merged[index].0))
+            .collect::<Vec<_>>();
+        let mut in_file = slots.iter().map(|&index| merged[index]).collect::<Vec<_>>();
+        in_file.sort_by_key(|(key, _)| disk_order[key]);
+
+        for (index, song) in slots.into_iter().zip(in_file) {
+            merged[index] = song;
+        }
+    }
+
+    merged.into_iter().map(|(_, song)| song.clone()).collect()
+}
+
+/// what a song is matched by, see `keyed`
+type Key = (String, String, usize);
+
+/// songs are the same song if they have the same id in the song library, or
+/// if they play the same part of the same file
+fn song_key(song: &Value) -> (String, String) {
+    if let Some(id) = song["id"].as_str().filter(|id| !id.is_empty()) {
+        return (id.to_string(), String::new());
+    }
+
+    (
+        song["path_to_song"].to_string(),
+        song["start_at"].as_f64().unwrap_or_default().to_string(),
+    )
+}
+
+/// the songs with their keys. A song can be in a playlist more than once, so
+/// the key also has how many times the song came before it
+fn keyed(songs: &[Value]) -> Vec<(Key, &Value)> {
+    let mut seen = HashMap::new();
+
+    songs
+        .iter()
+        .map(|song| {
+            let (first, second) = song_key(song);
+            let count = seen.entry((first.clone(), second.clone())).or_insert(0);
+            *count += 1;
+
+            ((first, second, *count - 1), song)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{merge_objects, merge_playlist};
+
+    fn song(path: &str, play_count: u32) -> serde_json::Value {
+        json!({ "path_to_song": path, "play_count": play_count })
+    }
+
+    #[test]
+    fn objects() {
+        let base = json!({ "volume": 30, "playback_mode": "Sequel" });
+        let ours = json!({ "volume": 50, "playback_mode": "Sequel" });
+        let disk = json!({ "volume": 30, "playback_mode": "Random", "visualizer": "Bars" });
+
+        assert_eq!(
+            merge_objects(Some(&base), &ours, &disk),
+            json!({ "volume": 50, "playback_mode": "Random", "visualizer": "Bars" })
+        );
+    }
+
+    #[test]
+    fn songs_from_both_sides_are_kept() {
+        let base = json!({ "songs": [song("a", 0), song("b", 0), song("c", 0)] });
+        // played "a" and removed "b" here
+        let ours = json!({ "songs": [song("a", 1), song("c", 0)] });
+        // removed "c", played "b" and added "d" somewhere else
+        let disk = json!({ "songs": [song("a", 0), song("b", 1), song("d", 0)] });
+
+        assert_eq!(
+            merge_playlist(Some(&base), &ours, &disk)["songs"],
+            json!([song("a", 1), song("d", 0)])
+        );
+    }
+
+    #[test]
+    fn songs_with_ids_are_matched_by_id() {
+        let renamed = json!({ "id": "1", "path_to_song": "b" });
+        let base = json!({ "songs": [{ "id": "1", "path_to_song": "a" }] });
+        let ours = json!({ "songs": [renamed] });
+
+        assert_eq!(
+            merge_playlist(Some(&base), &ours, &base)["songs"],
+            json!([renamed])
+        );
+    }
+
+    #[test]
+    fn songs_in_the_playlist_twice_are_kept() {
+        let base = json!({ "songs": [song("a", 0), song("a", 0)] });
+        let ours = json!({ "songs": [song("a", 0), song("a", 1)] });
+        // the second one was removed somewhere else
+        let disk = json!({ "songs": [song("a", 2)] });
+
+        assert_eq!(
+            merge_playlist(Some(&base), &ours, &base)["songs"],
+            json!([song("a", 0), song("a", 1)])
+        );
+        // the first one is taken from the file, the changed second one is
+        // kept
+        assert_eq!(
+            merge_playlist(Some(&base), &ours, &disk)["songs"],
+            json!([song("a", 2), song("a", 1)])
+        );
+    }
+
+    #[test]
+    fn order_from_disk_is_kept_if_not_reordered_here() {
+        let base = json!({ "songs": [song("a", 0), song("b", 0), song("c", 0)] });
+        let disk = json!({ "songs": [song("c", 0), song("b", 0), song("a", 0)] });
+
+        assert_eq!(
+            merge_playlist(Some(&base), &base, &disk)["songs"],
+            disk["songs"]
+        );
+
+        // a song added in front here stays in front
+        let ours = json!({ "songs": [song("d", 0), song("a", 0), song("b", 0), song("c", 0)] });
+        assert_eq!(
+            merge_playlist(Some(&base), &ours, &disk)["songs"],
+            json!([song("d", 0), song("c", 0), song("b", 0), song("a", 0)])
+        );
+
+        // reordered on both sides, the order in memory wins
+        let ours = json!({ "songs": [song("b", 0), song("a", 0), song("c", 0)] });
+        assert_eq!(
+            merge_playlist(Some(&base), &ours, &disk)["songs"],
+            ours["songs"]
+        );
+    }
+
+    #[test]
+    fn changes_from_disk_are_taken() {
+        let base = json!({ "songs": [song("a", 0)] });
+        let disk = json!({ "songs": [song("a", 3)] });
+
+        assert_eq!(
+            merge_playlist(Some(&base), &base, &disk)["songs"],
+            json!([song("a", 3)])
+        );
+        assert_eq!(
+            merge_playlist(None, &json!({ "songs": [] }), &disk)["songs"],
+            json!([song("a", 3)])
+        );
+    }
+}
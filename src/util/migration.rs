@@ -0,0 +1,177 @@
+use anyhow::anyhow;
+use chrono::DateTime;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// The version of the layout of `info.json`
+///
+/// - 0: the layout in `example-playlist.json`. Songs are under `playlist` and
+///   have a `duration`
+/// - 1: songs are under `songs`, no version is written
+/// - 2: the version is written into the file
+/// - 3: songs are stored in the song library, and the playlist has their ids.
+///   Songs of older playlists are moved into the library when they are loaded
+pub const PLAYLIST_VERSION: u32 = 3;
+
+/// The version of the layout of the song library file
+///
+/// - 1: the first version
+pub const LIBRARY_VERSION: u32 = 1;
+
+/// The version of the layout of the settings file
+///
+/// - 0: no version is written
+/// - 1: the version is written into the file
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Writes the version in front of the fields of the data
+#[derive(Serialize)]
+pub struct Versioned<'a, T: Serialize> {
+    pub version: u32,
+
+    #[serde(flatten)]
+    pub data: &'a T,
+}
+
+/// the version of a playlist that was read from `info.json`
+pub fn playlist_version(json: &Value) -> u32 {
+    match json.get("version").and_then(Value::as_u64) {
+        Some(version) => version as u32,
+        None if json.get("playlist").is_some() => 0,
+        None => 1,
+    }
+}
+
+pub fn settings_version(json: &Value) -> u32 {
+    json.get("version")
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(0)
+}
+
+/// upgrade a playlist read from `info.json` to the current version
+pub fn migrate_playlist(mut json: Value) -> anyhow::Result<Value> {
+    let version = playlist_version(&json);
+
+    if version > PLAYLIST_VERSION {
+        return Err(anyhow!(
+            "The playlist is made by a newer version of the music player (version {})",
+            version
+        ));
+    }
+
+    let object = as_object(&mut json)?;
+
+    if version < 1 {
+        playlist_v0_to_v1(object);
+    }
+
+    object.insert("version".to_string(), PLAYLIST_VERSION.into());
+
+    Ok(json)
+}
+
+/// upgrade the settings to the current version
+pub fn migrate_settings(mut json: Value) -> anyhow::Result<Value> {
+    let version = settings_version(&json);
+
+    if version > SETTINGS_VERSION {
+        return Err(anyhow!(
+            "The settings are made by a newer version of the music player (version {})",
+            version
+        ));
+    }
+
+    as_object(&mut json)?.insert("version".to_string(), SETTINGS_VERSION.into());
+
+    Ok(json)
+}
+
+/// check that the song library can be read by this version
+pub fn migrate_library(json: Value) -> anyhow::Result<Value> {
+    let version = json.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+
+    if version > LIBRARY_VERSION {
+        return Err(anyhow!(
+            "The song library is made by a newer version of the music player (version {})",
+            version
+        ));
+    }
+
+    Ok(json)
+}
+
+fn as_object(json: &mut Value) -> anyhow::Result<&mut Map<String, Value>> {
+    json.as_object_mut()
+        .ok_or_else(|| anyhow!("Expected a json object"))
+}
+
+fn playlist_v0_to_v1(object: &mut Map<String, Value>) {
+    // the name comes from the folder now
+    object.remove("name");
+
+    let mut songs = object
+        .remove("playlist")
+        .unwrap_or_else(|| Value::Array(Vec::new()));
+
+    // the duration is read from the audio file now
+    for song in songs.as_array_mut().into_iter().flatten() {
+        if let Some(song) = song.as_object_mut() {
+            song.remove("duration");
+        }
+    }
+
+    object.insert("songs".to_string(), songs);
+
+    // dates used to be written in any format
+    let created_is_valid = object
+        .get("created")
+        .and_then(Value::as_str)
+        .is_some_and(|created| DateTime::parse_from_rfc3339(created).is_ok());
+
+    if !created_is_valid {
+        object.insert("created".to_string(), Value::Null);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::cli::data::PlaylistInfo;
+
+    use super::{
+        migrate_playlist, migrate_settings, playlist_version, PLAYLIST_VERSION, SETTINGS_VERSION,
+    };
+
+    #[test]
+    fn example_playlist() {
+        let json = serde_json::from_str(include_str!("../../example-playlist.json")).unwrap();
+        assert_eq!(playlist_version(&json), 0);
+
+        let json = migrate_playlist(json).unwrap();
+        assert_eq!(playlist_version(&json), PLAYLIST_VERSION);
+        assert!(json["songs"][0].get("duration").is_none());
+
+        let playlist_info: PlaylistInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(playlist_info.songs.len(), 2);
+        assert_eq!(playlist_info.songs[0].song_name, "name");
+        assert!(playlist_info.created.is_none());
+    }
+
+    #[test]
+    fn unversioned_playlist() {
+        let json = json!({ "songs": [], "created": "2022-03-25T10:00:00+00:00" });
+        assert_eq!(playlist_version(&json), 1);
+
+        let migrated = migrate_playlist(json.clone()).unwrap();
+        assert_eq!(migrated["version"], PLAYLIST_VERSION);
+        assert_eq!(migrated["created"], json["created"]);
+    }
+
+    #[test]
+    fn newer_versions_fail() {
+        assert!(migrate_playlist(json!({ "version": PLAYLIST_VERSION + 1 })).is_err());
+        assert!(migrate_settings(json!({ "version": SETTINGS_VERSION + 1 })).is_err());
+    }
+}
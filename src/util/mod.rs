@@ -1,19 +1,42 @@
-use std::{path::PathBuf, str::FromStr, time::Instant};
+use std::{cmp::Reverse, path::PathBuf, str::FromStr, time::Instant};
 
 use anyhow::Context;
+#[cfg(not(test))]
 use basic_quick_lib::home_dir::home_dir;
+use basic_quick_lib::time::LocalTime;
 use once_cell::sync::Lazy;
 use rand::{seq::SliceRandom, thread_rng};
 use regex::Regex;
 use std::io::Write;
 use termcolor::{ColorChoice, ColorSpec, StandardStream, WriteColor};
 
-use crate::cli::data::PlaylistInfo;
+use crate::cli::data::{PlaylistInfo, Song};
 
-use self::yt_downloader::YTDownload;
+use self::{
+    storage::storage,
+    yt_downloader::{get_chapters, split_into_chapters, YTDownload},
+};
 
+pub mod audio;
+pub mod backup;
+pub mod bundle;
 pub mod colored;
+pub mod dedupe;
+pub mod history;
+pub mod json_storage;
+pub mod library;
+pub mod lock;
+pub mod lyrics;
+pub mod merge;
+pub mod migration;
+pub mod playlist_file;
+pub mod render;
 pub mod settings;
+pub mod song_library;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_storage;
+pub mod storage;
+pub mod visualizer;
 pub mod youtube_api;
 pub mod yt_downloader;
 
@@ -49,17 +72,51 @@ pub enum ToIndexError {
     GetIndexError(GetIndexError),
 }
 
-pub fn playlist_info_folder(playlist_name: &str) -> PathBuf {
-    let mut path_to_json = PathBuf::from_str(home_dir().as_str())
+/// the folder the playlists, the settings and the downloads are kept in. It is
+/// the home directory, except in tests, which get a temporary folder so they
+/// never touch the real playlists
+pub fn home_folder() -> String {
+    #[cfg(not(test))]
+    return home_dir();
+
+    #[cfg(test)]
+    return TEST_HOME.to_string_lossy().to_string();
+}
+
+/// a new folder for every run of the tests
+#[cfg(test)]
+static TEST_HOME: Lazy<PathBuf> = Lazy::new(|| {
+    let path =
+        std::env::temp_dir().join(format!("rust-cli-music-player-test-{}", std::process::id()));
+
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).unwrap();
+
+    path
+});
+
+pub fn playlists_folder() -> PathBuf {
+    let mut path = PathBuf::from_str(home_folder().as_str())
         .with_context(|| "Failed to load home dir as path buf")
         .unwrap();
 
-    path_to_json.push(PLAYLIST_DIR);
+    path.push(PLAYLIST_DIR);
+
+    path
+}
+
+pub fn playlist_info_folder(playlist_name: &str) -> PathBuf {
+    let mut path_to_json = playlists_folder();
     path_to_json.push(playlist_name);
 
     path_to_json
 }
 
+/// get the names of every playlist, sorted by name
+pub fn playlist_names() -> anyhow::Result<Vec<String>> {
+    storage().playlist_names()
+}
+
 pub fn playlist_info_path(playlist_name: &str) -> PathBuf {
     let mut path_to_json = playlist_info_folder(playlist_name);
     path_to_json.push("info");
@@ -68,15 +125,69 @@ pub fn playlist_info_path(playlist_name: &str) -> PathBuf {
     path_to_json
 }
 
-pub fn create_playlist(playlist_name: &str) {
-    let playlist_info = PlaylistInfo::new(playlist_name);
-    playlist_info.save();
+pub fn create_playlist(playlist_name: &str) -> anyhow::Result<()> {
+    let mut playlist_info = PlaylistInfo::new(playlist_name);
+    playlist_info.save()
 }
 
 pub fn help_print(command: &str, help_msg: &str) {
     println!("{:<30} --- {}", command, help_msg);
 }
 
+/// print the songs whose files can't be found, after how many there are and
+/// the message, like `2 songs can't be found:`. Nothing is printed if there
+/// are none
+pub fn print_missing<'a>(songs: impl IntoIterator<Item = &'a Song>, message: &str) {
+    let songs = songs.into_iter().collect::<Vec<_>>();
+
+    if songs.is_empty() {
+        return;
+    }
+
+    println!("{} {}:", songs.len(), message);
+
+    for song in songs {
+        println!("    {} ({})", song.song_name, song.path().display());
+    }
+}
+
+/// format seconds into `m:ss`, or `h:mm:ss` if it is longer than an hour
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// the date, or `default` if there is none
+pub fn date_or(date: &Option<LocalTime>, default: &str) -> String {
+    date.as_ref()
+        .map(|d| d.to_date_string())
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// format a number of bytes with the biggest unit that fits, like `3.4 MB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
 pub fn multiplied_volume(volume: u8, multiplier: f32) -> f32 {
     (volume as f32 * multiplier / 100.0).clamp(0.0, 1.0)
 }
@@ -100,6 +211,23 @@ pub fn get_index(index: i32, vec_len: usize) -> Result<usize, GetIndexError> {
     Ok(vec_index as usize)
 }
 
+/// sort by the key of every item, or the other way around if `reverse` is
+/// set. Items without a key go to the end either way, and items with the same
+/// key keep their order. The key of every item is only worked out once
+pub fn sort_by<T, K: Ord>(items: &mut [T], reverse: bool, key: impl Fn(&T) -> Option<K>) {
+    if reverse {
+        items.sort_by_cached_key(|item| {
+            let key = key(item);
+            (key.is_none(), key.map(Reverse))
+        });
+    } else {
+        items.sort_by_cached_key(|item| {
+            let key = key(item);
+            (key.is_none(), key)
+        });
+    }
+}
+
 pub fn shuffle_vec(vec: &mut Vec<usize>, target_len: usize) {
     *vec = (0..target_len).collect();
     vec.shuffle(&mut thread_rng());
@@ -131,7 +259,13 @@ pub fn to_index(args: &[&str], index: usize, song_len: usize) -> Result<usize, T
     Ok(index)
 }
 
-pub fn add_from_youtube_link(playlist_name: &str, link: &str) -> anyhow::Result<()> {
+/// download the video and add it to the playlist. If `split_chapters` is on,
+/// a video with chapters is added as one song per chapter
+pub fn add_from_youtube_link(
+    playlist_name: &str,
+    link: &str,
+    split_chapters: bool,
+) -> anyhow::Result<()> {
     let start = Instant::now();
 
     let mut playlist_info = PlaylistInfo::load_or_create(playlist_name);
@@ -143,10 +277,36 @@ pub fn add_from_youtube_link(playlist_name: &str, link: &str) -> anyhow::Result<
     path.pop();
     let path = format!("{}\\%(id)s.%(ext)s", path.to_string_lossy());
 
-    download_config.output_path(path).download()?;
+    // songs are named after the video, so the same video is only downloaded
+    // once and the song in the library is used again
+    if song.path_to_song.exists() {
+        println!("The video is already downloaded, using the downloaded file");
+    } else {
+        download_config.output_path(path).download()?;
+    }
+
+    let chapters = if split_chapters {
+        get_chapters(&song.path_to_song).unwrap_or_else(|e| {
+            println!("Failed to read the chapters, adding it as one song. Error: {e}");
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+
+    let song = Song {
+        path_to_song: library::to_stored(&song.path_to_song),
+        ..song
+    };
+
+    let songs = split_into_chapters(song, &chapters);
+
+    if songs.len() > 1 {
+        println!("Split the video into {} songs by its chapters", songs.len());
+    }
 
-    playlist_info.songs.push(song);
-    playlist_info.save();
+    playlist_info.songs.extend(songs);
+    playlist_info.save()?;
 
     let end = Instant::now();
 
@@ -180,11 +340,37 @@ pub fn decode_html_entities(str: &str) -> String {
 
 #[cfg(test)]
 mod test {
-    use super::playlist_info_path;
+    use super::{format_duration, format_size, playlist_info_path, sort_by};
 
     #[test]
     fn playlist_info_path_test() {
         let test = playlist_info_path("test");
         println!("{:?}", test);
     }
+
+    #[test]
+    fn format_duration_test() {
+        assert_eq!(format_duration(0), "0:00");
+        assert_eq!(format_duration(185), "3:05");
+        assert_eq!(format_duration(3725), "1:02:05");
+    }
+
+    #[test]
+    fn sort_by_test() {
+        let mut items = [("a", Some(2)), ("b", None), ("c", Some(1)), ("d", Some(2))];
+
+        sort_by(&mut items, false, |(_, key)| *key);
+        assert_eq!(items.map(|(name, _)| name), ["c", "a", "d", "b"]);
+
+        // the same key keeps the order, and no key is still last
+        sort_by(&mut items, true, |(_, key)| *key);
+        assert_eq!(items.map(|(name, _)| name), ["a", "d", "c", "b"]);
+    }
+
+    #[test]
+    fn format_size_test() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+    }
 }
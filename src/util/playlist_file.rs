@@ -0,0 +1,437 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use quick_xml::{events::Event, Reader};
+
+/// Playlist files other players understand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    M3u,
+    M3u8,
+    Xspf,
+    Pls,
+}
+
+impl PlaylistFormat {
+    /// guess the format from the extension of the file
+    pub fn from_path(path: &Path) -> anyhow::Result<Self> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .ok_or_else(|| anyhow!("The file has no extension! Use --format to set the format"))?
+            .parse()
+    }
+}
+
+impl FromStr for PlaylistFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "m3u" => Ok(Self::M3u),
+            "m3u8" => Ok(Self::M3u8),
+            "xspf" => Ok(Self::Xspf),
+            "pls" => Ok(Self::Pls),
+            _ => Err(anyhow!(
+                "Unknown playlist format! Valid ones are: m3u, m3u8, xspf, pls"
+            )),
+        }
+    }
+}
+
+impl Display for PlaylistFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::M3u => write!(f, "m3u"),
+            Self::M3u8 => write!(f, "m3u8"),
+            Self::Xspf => write!(f, "xspf"),
+            Self::Pls => write!(f, "pls"),
+        }
+    }
+}
+
+/// A song as it is written in a playlist file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub duration: Option<Duration>,
+}
+
+/// write the entries into a playlist file. Formats that have a title for the
+/// whole playlist get `title`
+pub fn write(format: PlaylistFormat, title: &str, entries: &[Entry]) -> String {
+    match format {
+        PlaylistFormat::M3u | PlaylistFormat::M3u8 => write_m3u(entries),
+        PlaylistFormat::Xspf => write_xspf(title, entries),
+        PlaylistFormat::Pls => write_pls(entries),
+    }
+}
+
+/// read the entries of a playlist file. Relative paths are kept relative
+pub fn parse(format: PlaylistFormat, data: &str) -> anyhow::Result<Vec<Entry>> {
+    match format {
+        PlaylistFormat::M3u | PlaylistFormat::M3u8 => Ok(parse_m3u(data)),
+        PlaylistFormat::Xspf => parse_xspf(data),
+        PlaylistFormat::Pls => Ok(parse_pls(data)),
+    }
+}
+
+/// `<author> - <title>` for the formats that only have a title
+fn full_title(entry: &Entry) -> String {
+    match (&entry.author, &entry.title) {
+        (Some(author), Some(title)) => format!("{} - {}", author, title),
+        (None, Some(title)) => title.clone(),
+        (_, None) => String::new(),
+    }
+}
+
+/// split `<author> - <title>` back into the author and the title
+fn split_title(title: &str) -> (Option<String>, Option<String>) {
+    let (author, title) = match title.split_once(" - ") {
+        Some((author, title)) => (Some(author.trim()), title.trim()),
+        None => (None, title.trim()),
+    };
+
+    (
+        author.map(|s| s.to_string()).filter(|s| !s.is_empty()),
+        Some(title.to_string()).filter(|s| !s.is_empty()),
+    )
+}
+
+/// -1 means the length is unknown
+fn seconds_or_unknown(duration: Option<Duration>) -> i64 {
+    duration
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(-1)
+}
+
+fn parse_seconds(seconds: &str) -> Option<Duration> {
+    seconds
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| *seconds >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+fn write_m3u(entries: &[Entry]) -> String {
+    let mut m3u = String::from("#EXTM3U\n");
+
+    for entry in entries {
+        let _ = writeln!(
+            m3u,
+            "#EXTINF:{},{}",
+            seconds_or_unknown(entry.duration),
+            full_title(entry)
+        );
+        let _ = writeln!(m3u, "{}", entry.path.display());
+    }
+
+    m3u
+}
+
+fn parse_m3u(data: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut info = Entry::default();
+
+    // a bom would end up in front of the first line
+    for line in data.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+
+        if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+            info = parse_extinf(extinf);
+        } else if line.is_empty() || line.starts_with('#') {
+            continue;
+        } else {
+            entries.push(Entry {
+                path: PathBuf::from(line),
+                ..std::mem::take(&mut info)
+            });
+        }
+    }
+
+    entries
+}
+
+/// parse `<seconds>,<author> - <title>`. Attributes some players put before
+/// the comma are ignored
+fn parse_extinf(extinf: &str) -> Entry {
+    let (seconds, title) = extinf.split_once(',').unwrap_or((extinf, ""));
+
+    let duration = seconds.split_whitespace().next().and_then(parse_seconds);
+    let (author, title) = split_title(title);
+
+    Entry {
+        path: PathBuf::new(),
+        title,
+        author,
+        duration,
+    }
+}
+
+fn write_pls(entries: &[Entry]) -> String {
+    let mut pls = String::from("[playlist]\n");
+
+    for (index, entry) in entries.iter().enumerate() {
+        let n = index + 1;
+
+        let _ = writeln!(pls, "File{}={}", n, entry.path.display());
+        let _ = writeln!(pls, "Title{}={}", n, full_title(entry));
+        let _ = writeln!(pls, "Length{}={}", n, seconds_or_unknown(entry.duration));
+    }
+
+    let _ = writeln!(pls, "NumberOfEntries={}", entries.len());
+    let _ = writeln!(pls, "Version=2");
+
+    pls
+}
+
+fn parse_pls(data: &str) -> Vec<Entry> {
+    // entries are numbered, and the lines of an entry can be in any order
+    let mut entries = BTreeMap::<u32, Entry>::new();
+
+    for line in data.trim_start_matches('\u{feff}').lines() {
+        let (key, value) = match line.trim().split_once('=') {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let key = key.trim().to_lowercase();
+        let split_at = key.find(|c: char| c.is_ascii_digit()).unwrap_or(key.len());
+        let (field, n) = key.split_at(split_at);
+
+        let n = match n.parse() {
+            Ok(n) => n,
+            Err(_) => continue,
+        };
+        let entry = entries.entry(n).or_default();
+
+        match field {
+            "file" => entry.path = PathBuf::from(value.trim()),
+            "title" => (entry.author, entry.title) = split_title(value),
+            "length" => entry.duration = parse_seconds(value),
+            _ => {}
+        }
+    }
+
+    entries
+        .into_values()
+        .filter(|entry| !entry.path.as_os_str().is_empty())
+        .collect()
+}
+
+fn write_xspf(title: &str, entries: &[Entry]) -> String {
+    let mut xspf = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n",
+    );
+
+    let _ = writeln!(xspf, "  <title>{}</title>", escape(title));
+    xspf.push_str("  <trackList>\n");
+
+    for entry in entries {
+        xspf.push_str("    <track>\n");
+        let _ = writeln!(
+            xspf,
+            "      <location>{}</location>",
+            escape(&to_location(&entry.path))
+        );
+
+        if let Some(title) = &entry.title {
+            let _ = writeln!(xspf, "      <title>{}</title>", escape(title));
+        }
+        if let Some(author) = &entry.author {
+            let _ = writeln!(xspf, "      <creator>{}</creator>", escape(author));
+        }
+        if let Some(duration) = entry.duration {
+            let _ = writeln!(xspf, "      <duration>{}</duration>", duration.as_millis());
+        }
+
+        xspf.push_str("    </track>\n");
+    }
+
+    xspf.push_str("  </trackList>\n</playlist>\n");
+
+    xspf
+}
+
+fn parse_xspf(data: &str) -> anyhow::Result<Vec<Entry>> {
+    let mut reader = Reader::from_str(data);
+    reader.trim_text(true);
+
+    let mut entries = Vec::new();
+    let mut track = None;
+    let mut element = Vec::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => {
+                element = start.local_name().as_ref().to_vec();
+
+                if element == b"track" {
+                    track = Some(Entry::default());
+                }
+            }
+            Event::Text(text) => {
+                let track = match &mut track {
+                    Some(v) => v,
+                    None => continue,
+                };
+                let text = text.unescape()?.trim().to_string();
+
+                match element.as_slice() {
+                    b"location" => track.path = from_location(&text),
+                    b"title" => track.title = Some(text),
+                    b"creator" => track.author = Some(text),
+                    b"duration" => track.duration = text.parse().ok().map(Duration::from_millis),
+                    _ => {}
+                }
+            }
+            Event::End(end) => {
+                if end.local_name().as_ref() == b"track" {
+                    entries.extend(track.take().filter(|t| !t.path.as_os_str().is_empty()));
+                }
+
+                element.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+/// characters that can't be in a uri as they are
+const URI_ESCAPE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// xspf locations are uris. Absolute paths become `file://` uris, and
+/// relative paths stay relative
+fn to_location(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let encoded = utf8_percent_encode(&path, URI_ESCAPE).to_string();
+
+    if encoded.starts_with('/') {
+        format!("file://{}", encoded)
+    } else if encoded.chars().nth(1) == Some(':') {
+        // a windows path like C:/music
+        format!("file:///{}", encoded)
+    } else {
+        encoded
+    }
+}
+
+fn from_location(location: &str) -> PathBuf {
+    let path = match location.strip_prefix("file://") {
+        // file:///C:/music on windows
+        Some(path) if path.chars().nth(2) == Some(':') => &path[1..],
+        Some(path) => path,
+        None => location,
+    };
+
+    PathBuf::from(percent_decode_str(path).decode_utf8_lossy().to_string())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::PathBuf, time::Duration};
+
+    use super::{parse, write, Entry, PlaylistFormat};
+
+    fn entries() -> Vec<Entry> {
+        vec![
+            Entry {
+                path: PathBuf::from("/music/song.wav"),
+                title: Some("Song".to_string()),
+                author: Some("Someone".to_string()),
+                duration: Some(Duration::from_secs(182)),
+            },
+            Entry {
+                path: PathBuf::from("other.wav"),
+                title: Some("Other".to_string()),
+                author: None,
+                duration: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn m3u_round_trip() {
+        let m3u = write(PlaylistFormat::M3u8, "playlist", &entries());
+
+        assert_eq!(
+            m3u,
+            "#EXTM3U\n#EXTINF:182,Someone - Song\n/music/song.wav\n#EXTINF:-1,Other\nother.wav\n"
+        );
+        assert_eq!(parse(PlaylistFormat::M3u8, &m3u).unwrap(), entries());
+    }
+
+    #[test]
+    fn xspf_and_pls_round_trip() {
+        let mut entries = entries();
+        entries[0].path = PathBuf::from("/my music/song #1.wav");
+
+        for format in [PlaylistFormat::Xspf, PlaylistFormat::Pls] {
+            let data = write(format, "a & b", &entries);
+            assert_eq!(parse(format, &data).unwrap(), entries, "{}", format);
+        }
+
+        let xspf = write(PlaylistFormat::Xspf, "a & b", &entries);
+        assert!(xspf.contains("<title>a &amp; b</title>"));
+        assert!(xspf.contains("<location>file:///my%20music/song%20%231.wav</location>"));
+    }
+
+    #[test]
+    fn pls_entries_in_any_order() {
+        let entries = parse(
+            PlaylistFormat::Pls,
+            "[playlist]\nTitle2=Second\nFile2=b.wav\nFile1=a.wav\nLength1=-1\nNumberOfEntries=2",
+        )
+        .unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, PathBuf::from("a.wav"));
+        assert_eq!(entries[1].title.as_deref(), Some("Second"));
+    }
+
+    #[test]
+    fn plain_m3u() {
+        let entries = parse(PlaylistFormat::M3u, "# a comment\r\nsong.mp3\r\n\r\n").unwrap();
+
+        assert_eq!(
+            entries,
+            vec![Entry {
+                path: PathBuf::from("song.mp3"),
+                ..Default::default()
+            }]
+        );
+    }
+}
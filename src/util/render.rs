@@ -0,0 +1,273 @@
+use std::{
+    io::{Seek, Write},
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+
+pub const SAMPLE_RATE: u32 = 44100;
+pub const CHANNELS: u16 = 2;
+
+pub fn output_spec() -> WavSpec {
+    WavSpec {
+        channels: CHANNELS,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    }
+}
+
+/// decode a wav file into interleaved stereo samples at `SAMPLE_RATE`
+pub fn decode_wav(path: &Path) -> anyhow::Result<Vec<f32>> {
+    // a file that can't be opened is not blamed on its format
+    let reader = WavReader::open(path).map_err(|e| match e {
+        hound::Error::IoError(e) => {
+            anyhow::Error::new(e).context(format!("Failed to open {}", path.display()))
+        }
+        e => anyhow::Error::new(e).context(format!("{} is not a wav file", path.display())),
+    })?;
+    let spec = reader.spec();
+
+    let samples = match spec.sample_format {
+        SampleFormat::Float => reader
+            .into_samples::<f32>()
+            .collect::<Result<Vec<_>, _>>()?,
+        SampleFormat::Int => {
+            let max = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|s| s as f32 / max))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+
+    let stereo = to_stereo(&samples, spec.channels)?;
+
+    Ok(resample(&stereo, spec.sample_rate, SAMPLE_RATE))
+}
+
+/// turn interleaved samples with any channel count into stereo. Mono is
+/// copied to both sides and anything after the second channel is dropped
+pub fn to_stereo(samples: &[f32], channels: u16) -> anyhow::Result<Vec<f32>> {
+    match channels {
+        0 => Err(anyhow!("Audio has no channels")),
+        1 => Ok(samples.iter().flat_map(|&s| [s, s]).collect()),
+        2 => Ok(samples.to_vec()),
+        n => Ok(samples
+            .chunks_exact(n as usize)
+            .flat_map(|frame| [frame[0], frame[1]])
+            .collect()),
+    }
+}
+
+/// linearly resample interleaved stereo samples
+pub fn resample(stereo: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || stereo.is_empty() {
+        return stereo.to_vec();
+    }
+
+    let frames = stereo.len() / 2;
+    let new_frames = (frames as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let ratio = from_rate as f64 / to_rate as f64;
+
+    let mut resampled = Vec::with_capacity(new_frames * 2);
+
+    for frame in 0..new_frames {
+        let position = frame as f64 * ratio;
+        let index = position as usize;
+        let next = (index + 1).min(frames - 1);
+        let t = (position - index as f64) as f32;
+
+        for channel in 0..2 {
+            let a = stereo[index * 2 + channel];
+            let b = stereo[next * 2 + channel];
+            resampled.push(a + (b - a) * t);
+        }
+    }
+
+    resampled
+}
+
+/// the part of the decoded samples between start and end
+pub fn trim(stereo: &[f32], start: Duration, end: Option<Duration>) -> &[f32] {
+    let to_index =
+        |time: Duration| ((time.as_secs_f64() * SAMPLE_RATE as f64) as usize * 2).min(stereo.len());
+
+    let start = to_index(start);
+    let end = end.map(to_index).unwrap_or(stereo.len()).max(start);
+
+    &stereo[start..end]
+}
+
+/// Writes songs one after another into a wav file, fading from one to the
+/// next if crossfade is on. Only the part that might still be crossfaded is
+/// kept in memory
+pub struct Mixer<W: Write + Seek> {
+    writer: WavWriter<W>,
+    crossfade_samples: usize,
+    tail: Vec<f32>,
+    written: usize,
+}
+
+impl<W: Write + Seek> Mixer<W> {
+    pub fn new(writer: WavWriter<W>, crossfade_seconds: f32) -> Self {
+        let crossfade_frames = (crossfade_seconds.max(0.0) * SAMPLE_RATE as f32) as usize;
+
+        Self {
+            writer,
+            crossfade_samples: crossfade_frames * CHANNELS as usize,
+            tail: Vec::new(),
+            written: 0,
+        }
+    }
+
+    pub fn add_track(&mut self, samples: &[f32], volume: f32) -> anyhow::Result<()> {
+        let overlap = self.tail.len().min(samples.len()) / 2 * 2;
+        let overlap_frames = (overlap / 2).max(1) as f32;
+
+        let tail = std::mem::take(&mut self.tail);
+        let (before, fading_out) = tail.split_at(tail.len() - overlap);
+        self.write(before)?;
+
+        let mut current = Vec::with_capacity(samples.len());
+
+        for (i, sample) in samples.iter().enumerate() {
+            let sample = sample * volume;
+
+            if i < overlap {
+                let fade_in = (i / 2) as f32 / overlap_frames;
+                current.push(fading_out[i] * (1.0 - fade_in) + sample * fade_in);
+            } else {
+                current.push(sample);
+            }
+        }
+
+        let keep = self.crossfade_samples.min(current.len()) / 2 * 2;
+        let (ready, tail) = current.split_at(current.len() - keep);
+        self.write(ready)?;
+        self.tail = tail.to_vec();
+
+        Ok(())
+    }
+
+    /// write what is left and return how many seconds were written in total
+    pub fn finish(mut self) -> anyhow::Result<u64> {
+        let tail = std::mem::take(&mut self.tail);
+        self.write(&tail)?;
+        self.writer.finalize()?;
+
+        Ok((self.written / CHANNELS as usize) as u64 / SAMPLE_RATE as u64)
+    }
+
+    fn write(&mut self, samples: &[f32]) -> anyhow::Result<()> {
+        for sample in samples {
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.writer.write_sample(sample)?;
+        }
+
+        self.written += samples.len();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Cursor, time::Duration};
+
+    use hound::{WavReader, WavWriter};
+
+    use super::{output_spec, resample, to_stereo, trim, Mixer, SAMPLE_RATE};
+
+    fn render(tracks: &[(Vec<f32>, f32)], crossfade: f32) -> Vec<i16> {
+        let mut bytes = Cursor::new(Vec::new());
+        let writer = WavWriter::new(&mut bytes, output_spec()).unwrap();
+        let mut mixer = Mixer::new(writer, crossfade);
+
+        for (samples, volume) in tracks {
+            mixer.add_track(samples, *volume).unwrap();
+        }
+        mixer.finish().unwrap();
+
+        bytes.set_position(0);
+        WavReader::new(bytes)
+            .unwrap()
+            .into_samples::<i16>()
+            .map(|s| s.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn channels() {
+        assert_eq!(to_stereo(&[0.1, 0.2], 1).unwrap(), vec![0.1, 0.1, 0.2, 0.2]);
+        assert_eq!(
+            to_stereo(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6], 3).unwrap(),
+            vec![0.1, 0.2, 0.4, 0.5]
+        );
+        assert!(to_stereo(&[0.1], 0).is_err());
+    }
+
+    #[test]
+    fn resample_length() {
+        let stereo = vec![0.5; SAMPLE_RATE as usize];
+
+        assert_eq!(
+            resample(&stereo, SAMPLE_RATE / 2, SAMPLE_RATE).len(),
+            stereo.len() * 2
+        );
+        assert_eq!(resample(&stereo, SAMPLE_RATE, SAMPLE_RATE), stereo);
+    }
+
+    #[test]
+    fn trim_to_offsets() {
+        let stereo = vec![0.5; SAMPLE_RATE as usize * 2 * 3];
+        let one_second = SAMPLE_RATE as usize * 2;
+
+        assert_eq!(
+            trim(
+                &stereo,
+                Duration::from_secs(1),
+                Some(Duration::from_secs(2))
+            )
+            .len(),
+            one_second
+        );
+        assert_eq!(
+            trim(&stereo, Duration::from_secs(1), None).len(),
+            one_second * 2
+        );
+        assert!(trim(&stereo, Duration::from_secs(5), None).is_empty());
+    }
+
+    #[test]
+    fn tracks_are_appended() {
+        let one_second = SAMPLE_RATE as usize * 2;
+        let samples = render(
+            &[(vec![0.5; one_second], 1.0), (vec![0.5; one_second], 0.5)],
+            0.0,
+        );
+
+        assert_eq!(samples.len(), one_second * 2);
+        assert_eq!(samples[0], (0.5 * i16::MAX as f32) as i16);
+        assert_eq!(samples[one_second], (0.25 * i16::MAX as f32) as i16);
+    }
+
+    #[test]
+    fn crossfade_overlaps_tracks() {
+        let one_second = SAMPLE_RATE as usize * 2;
+        let samples = render(
+            &[(vec![0.5; one_second], 1.0), (vec![0.5; one_second], 1.0)],
+            0.5,
+        );
+
+        // half a second is shared by both songs
+        assert_eq!(samples.len(), one_second * 3 / 2);
+
+        // fading between two equally loud songs keeps the same loudness
+        let expected = (0.5 * i16::MAX as f32) as i16;
+        assert!(samples.iter().all(|&s| (s - expected).abs() <= 1));
+    }
+}
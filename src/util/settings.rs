@@ -1,30 +1,32 @@
-use std::{fmt::Display, fs, str::FromStr};
+use std::{fmt::Display, path::PathBuf, str::FromStr};
 
 use anyhow::anyhow;
-use basic_quick_lib::home_dir::home_dir;
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 
+use super::{
+    audio::AudioBackend,
+    home_folder, merge,
+    migration::{self, Versioned},
+    storage::storage,
+    visualizer::VisualizerMode,
+};
+
 pub static SETTINGS: Lazy<RwLock<Settings>> =
     Lazy::new(|| RwLock::new(Settings::read_settings().unwrap_or_default()));
 
 const SETTINGS_FILE: &str = "rust-cli-music-player_settings.json";
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub enum PlaybackMode {
     Sequel,
     LoopOnce,
+    #[default]
     LoopPlaylist,
     Random,
 }
 
-impl Default for PlaybackMode {
-    fn default() -> Self {
-        Self::LoopPlaylist
-    }
-}
-
 impl FromStr for PlaybackMode {
     type Err = anyhow::Error;
 
@@ -59,6 +61,29 @@ impl Display for PlaybackMode {
 pub struct Settings {
     pub volume: u8,
     pub playback_mode: PlaybackMode,
+
+    #[serde(default)]
+    pub visualizer: VisualizerMode,
+
+    #[serde(default)]
+    pub audio_backend: AudioBackend,
+
+    /// How many old versions of each playlist are kept
+    #[serde(default = "default_backup_count")]
+    pub backup_count: usize,
+
+    /// The folder song paths are stored relative to
+    #[serde(default)]
+    pub library_root: Option<PathBuf>,
+
+    /// The settings as they were in the file when they were last read or
+    /// saved. Used to find out what other processes changed when saving
+    #[serde(skip)]
+    base: Option<serde_json::Value>,
+}
+
+const fn default_backup_count() -> usize {
+    5
 }
 
 impl Default for Settings {
@@ -66,28 +91,55 @@ impl Default for Settings {
         Self {
             volume: 30,
             playback_mode: Default::default(),
+            visualizer: Default::default(),
+            audio_backend: Default::default(),
+            backup_count: default_backup_count(),
+            library_root: None,
+            base: None,
         }
     }
 }
 
 impl Settings {
-    fn path() -> String {
-        format!("{}\\{}", home_dir(), SETTINGS_FILE)
+    pub fn path() -> String {
+        format!("{}\\{}", home_folder(), SETTINGS_FILE)
     }
 
     pub fn read_settings() -> anyhow::Result<Self> {
-        let settings_path = Self::path();
+        let json = Self::read_json()?;
 
-        let this = serde_json::from_str::<Self>(fs::read_to_string(settings_path)?.as_str())?;
+        let mut this = serde_json::from_value::<Self>(json.clone())?;
+        this.base = Some(json);
 
         Ok(this)
     }
 
-    pub fn save(&self) -> anyhow::Result<()> {
-        let json_str = serde_json::to_string_pretty(self)?;
-        fs::write(Self::path(), json_str)?;
+    fn read_json() -> anyhow::Result<serde_json::Value> {
+        let json = storage()
+            .read_settings()?
+            .ok_or_else(|| anyhow!("The settings were never saved"))?;
 
-        Ok(())
+        migration::migrate_settings(json)
+    }
+
+    /// save the settings. Settings changed by other processes since they were
+    /// read are kept unless they were changed here too
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        let _lock = storage().lock_settings()?;
+
+        let ours = serde_json::to_value(&*self)?;
+        let merged = match Self::read_json() {
+            Ok(disk) => merge::merge_objects(self.base.as_ref(), &ours, &disk),
+            Err(_) => ours,
+        };
+
+        *self = serde_json::from_value(merged.clone())?;
+        self.base = Some(merged);
+
+        storage().write_settings(&serde_json::to_value(Versioned {
+            version: migration::SETTINGS_VERSION,
+            data: &*self,
+        })?)
     }
 }
 
@@ -97,7 +149,7 @@ mod test {
 
     #[test]
     fn save() -> anyhow::Result<()> {
-        let data = Settings::default();
+        let mut data = Settings::default();
         data.save()?;
 
         Ok(())
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+
+use crate::cli::data::Song;
+
+use super::storage::storage;
+
+/// Every song of every playlist. Playlists only store the ids of their songs,
+/// so a song that is in several playlists is stored once and changing it
+/// changes it everywhere
+#[derive(Debug, Default)]
+pub struct SongLibrary {
+    songs: Vec<Song>,
+
+    /// where each id is in `songs`
+    index: HashMap<String, usize>,
+}
+
+impl SongLibrary {
+    /// read the library. It is empty if no song was saved yet
+    pub fn load() -> anyhow::Result<Self> {
+        let mut this = Self::default();
+
+        for song in storage().read_songs()? {
+            this.insert(song);
+        }
+
+        Ok(this)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Song> {
+        self.index.get(id).map(|&i| &self.songs[i])
+    }
+
+    /// the song with the id. Fails if it is not in the library, since the
+    /// playlist would lose the song if it were saved without it
+    pub fn song(&self, id: &str) -> anyhow::Result<&Song> {
+        self.get(id).ok_or_else(|| {
+            anyhow!(
+                r#"Song "{}" is not in the song library! The library may be missing or broken"#,
+                id
+            )
+        })
+    }
+
+    /// the songs that play the same part of the same file
+    pub fn find(&self, song: &Song) -> Vec<&Song> {
+        self.songs
+            .iter()
+            .filter(|stored| {
+                stored.path_to_song == song.path_to_song
+                    && stored.start_at == song.start_at
+                    && stored.end_at == song.end_at
+            })
+            .collect()
+    }
+
+    /// add the song, or replace the song with the same id
+    pub fn insert(&mut self, song: Song) {
+        match self.index.get(&song.id) {
+            Some(&i) => self.songs[i] = song,
+            None => {
+                self.index.insert(song.id.clone(), self.songs.len());
+                self.songs.push(song);
+            }
+        }
+    }
+
+    /// write the songs into the library. Songs that are already in it are
+    /// replaced, the others are added
+    pub fn update(songs: &[Song]) -> anyhow::Result<()> {
+        storage().write_songs(songs)
+    }
+}
+
+/// a new random id for a song
+pub fn new_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::cli::data::Song;
+
+    use super::{new_id, SongLibrary};
+
+    fn song(id: &str, name: &str, start_at: Option<f64>) -> Song {
+        Song {
+            id: id.to_string(),
+            start_at,
+            ..Song::new(name.to_string(), PathBuf::from("song.wav"), None, 1.0)
+        }
+    }
+
+    #[test]
+    fn insert_and_find() {
+        let mut library = SongLibrary::default();
+
+        library.insert(song("1", "song", None));
+        library.insert(song("2", "chapter", Some(30.0)));
+        library.insert(song("1", "renamed", None));
+
+        assert_eq!(library.get("1").unwrap().song_name, "renamed");
+        assert!(library.song("3").is_err());
+
+        let found = library.find(&song("", "", None));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "1");
+        assert_eq!(library.find(&song("", "", Some(30.0)))[0].id, "2");
+    }
+
+    #[test]
+    fn ids_are_unique() {
+        assert_ne!(new_id(), new_id());
+        assert_eq!(new_id().len(), 16);
+    }
+}
@@ -0,0 +1,497 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail};
+use basic_quick_lib::time::LocalTime;
+use chrono::{DateTime, Local};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Map, Value};
+
+use crate::cli::data::Song;
+
+use super::{
+    history::PlayEvent,
+    lock::FileLock,
+    migration::PLAYLIST_VERSION,
+    storage::{database_path, Storage, StorageKind},
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS songs (
+    id TEXT PRIMARY KEY,
+    song_name TEXT NOT NULL,
+    path_to_song TEXT NOT NULL,
+    author TEXT,
+    sound_multiplier REAL NOT NULL,
+    play_count INTEGER NOT NULL,
+    skip_count INTEGER NOT NULL,
+    last_played TEXT,
+    date_added TEXT,
+    rating INTEGER,
+    favorite INTEGER NOT NULL,
+    lyrics_path TEXT,
+    start_at REAL,
+    end_at REAL
+);
+
+CREATE TABLE IF NOT EXISTS song_tags (
+    song_id TEXT NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (song_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS song_tags_by_tag ON song_tags(tag);
+
+CREATE TABLE IF NOT EXISTS playlists (
+    name TEXT PRIMARY KEY,
+    created TEXT
+);
+
+CREATE TABLE IF NOT EXISTS playlist_songs (
+    playlist TEXT NOT NULL REFERENCES playlists(name) ON DELETE CASCADE ON UPDATE CASCADE,
+    position INTEGER NOT NULL,
+    song_id TEXT NOT NULL,
+    PRIMARY KEY (playlist, position)
+);
+
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS history (
+    started TEXT NOT NULL,
+    playlist TEXT NOT NULL,
+    song_name TEXT NOT NULL,
+    author TEXT,
+    path_to_song TEXT NOT NULL,
+    seconds_listened INTEGER NOT NULL,
+    skipped INTEGER NOT NULL
+);
+";
+
+/// Everything is kept in one sqlite database. Songs and tags are tables of
+/// their own, so they can be queried without reading every playlist
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+    path: PathBuf,
+}
+
+impl SqliteStorage {
+    pub fn path() -> PathBuf {
+        database_path()
+    }
+
+    /// open the database, creating the tables if they do not exist yet
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let connection = Connection::open(path)?;
+
+        connection.pragma_update(None, "foreign_keys", true)?;
+        connection.busy_timeout(std::time::Duration::from_secs(5))?;
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// the lock file is named after the database and what is locked
+    fn lock(&self, name: &str) -> anyhow::Result<FileLock> {
+        let mut path = self.path.as_os_str().to_owned();
+        path.push(format!(".{}", name));
+
+        FileLock::exclusive(Path::new(&path))
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Sqlite
+    }
+
+    fn playlist_names(&self) -> anyhow::Result<Vec<String>> {
+        let connection = self.connection.lock();
+        let mut statement = connection.prepare("SELECT name FROM playlists ORDER BY name")?;
+        let names = statement
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+
+        Ok(names)
+    }
+
+    fn read_playlist(&self, name: &str) -> anyhow::Result<Value> {
+        let connection = self.connection.lock();
+
+        let created: Option<String> = connection
+            .query_row(
+                "SELECT created FROM playlists WHERE name = ?1",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| anyhow!(r#"There is no playlist named "{}""#, name))?;
+
+        let mut statement = connection
+            .prepare("SELECT song_id FROM playlist_songs WHERE playlist = ?1 ORDER BY position")?;
+        let songs = statement
+            .query_map([name], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(json!({
+            "version": PLAYLIST_VERSION,
+            "songs": songs,
+            "created": created,
+        }))
+    }
+
+    fn write_playlist(&self, name: &str, playlist: &Value) -> anyhow::Result<()> {
+        let ids = playlist["songs"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|id| {
+                id.as_str()
+                    .ok_or_else(|| anyhow!("Only the ids of the songs can be stored"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut connection = self.connection.lock();
+        let transaction = connection.transaction()?;
+
+        transaction.execute(
+            "INSERT INTO playlists (name, created) VALUES (?1, ?2)
+             ON CONFLICT (name) DO UPDATE SET created = excluded.created",
+            params![name, playlist["created"].as_str()],
+        )?;
+        transaction.execute("DELETE FROM playlist_songs WHERE playlist = ?1", [name])?;
+
+        for (position, id) in ids.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO playlist_songs (playlist, position, song_id) VALUES (?1, ?2, ?3)",
+                params![name, position, id],
+            )?;
+        }
+
+        Ok(transaction.commit()?)
+    }
+
+    fn delete_playlist(&self, name: &str) -> anyhow::Result<()> {
+        let deleted = self
+            .connection
+            .lock()
+            .execute("DELETE FROM playlists WHERE name = ?1", [name])?;
+
+        if deleted == 0 {
+            bail!(r#"There is no playlist named "{}""#, name);
+        }
+
+        Ok(())
+    }
+
+    fn rename_playlist(&self, name: &str, new_name: &str) -> anyhow::Result<()> {
+        if self.playlist_names()?.iter().any(|n| n == new_name) {
+            bail!(r#"Playlist "{}" already exists!"#, new_name);
+        }
+
+        // the songs of the playlist follow it by the foreign key
+        let renamed = self.connection.lock().execute(
+            "UPDATE playlists SET name = ?1 WHERE name = ?2",
+            [new_name, name],
+        )?;
+
+        if renamed == 0 {
+            bail!(r#"There is no playlist named "{}""#, name);
+        }
+
+        Ok(())
+    }
+
+    fn read_songs(&self) -> anyhow::Result<Vec<Song>> {
+        let connection = self.connection.lock();
+
+        let mut tags = HashMap::<String, Vec<String>>::new();
+        let mut statement =
+            connection.prepare("SELECT song_id, tag FROM song_tags ORDER BY song_id, position")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        for row in rows {
+            let (id, tag) = row?;
+            tags.entry(id).or_default().push(tag);
+        }
+
+        let mut statement = connection.prepare(
+            "SELECT id, song_name, path_to_song, author, sound_multiplier, play_count,
+                    skip_count, last_played, date_added, rating, favorite, lyrics_path,
+                    start_at, end_at
+             FROM songs",
+        )?;
+        let songs = statement
+            .query_map([], |row| {
+                let id: String = row.get(0)?;
+
+                Ok(Song {
+                    tags: tags.remove(&id).unwrap_or_default(),
+                    id,
+                    song_name: row.get(1)?,
+                    path_to_song: PathBuf::from(row.get::<_, String>(2)?),
+                    author: row.get(3)?,
+                    sound_multiplier: row.get(4)?,
+                    play_count: row.get(5)?,
+                    skip_count: row.get(6)?,
+                    last_played: to_time(row.get(7)?),
+                    date_added: to_time(row.get(8)?),
+                    rating: row.get(9)?,
+                    favorite: row.get(10)?,
+                    lyrics_path: row.get::<_, Option<String>>(11)?.map(PathBuf::from),
+                    start_at: row.get(12)?,
+                    end_at: row.get(13)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(songs)
+    }
+
+    fn write_songs(&self, songs: &[Song]) -> anyhow::Result<()> {
+        let mut connection = self.connection.lock();
+        let transaction = connection.transaction()?;
+
+        for song in songs {
+            transaction.execute(
+                "INSERT INTO songs (id, song_name, path_to_song, author, sound_multiplier,
+                                    play_count, skip_count, last_played, date_added, rating,
+                                    favorite, lyrics_path, start_at, end_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+                 ON CONFLICT (id) DO UPDATE SET
+                    song_name = excluded.song_name,
+                    path_to_song = excluded.path_to_song,
+                    author = excluded.author,
+                    sound_multiplier = excluded.sound_multiplier,
+                    play_count = excluded.play_count,
+                    skip_count = excluded.skip_count,
+                    last_played = excluded.last_played,
+                    date_added = excluded.date_added,
+                    rating = excluded.rating,
+                    favorite = excluded.favorite,
+                    lyrics_path = excluded.lyrics_path,
+                    start_at = excluded.start_at,
+                    end_at = excluded.end_at",
+                params![
+                    song.id,
+                    song.song_name,
+                    song.path_to_song.to_string_lossy(),
+                    song.author,
+                    song.sound_multiplier,
+                    song.play_count,
+                    song.skip_count,
+                    from_time(&song.last_played),
+                    from_time(&song.date_added),
+                    song.rating,
+                    song.favorite,
+                    song.lyrics_path
+                        .as_ref()
+                        .map(|path| path.to_string_lossy().to_string()),
+                    song.start_at,
+                    song.end_at,
+                ],
+            )?;
+
+            transaction.execute("DELETE FROM song_tags WHERE song_id = ?1", [&song.id])?;
+
+            for (position, tag) in song.tags.iter().enumerate() {
+                transaction.execute(
+                    "INSERT INTO song_tags (song_id, position, tag) VALUES (?1, ?2, ?3)",
+                    params![song.id, position, tag],
+                )?;
+            }
+        }
+
+        Ok(transaction.commit()?)
+    }
+
+    fn read_settings(&self) -> anyhow::Result<Option<Value>> {
+        let connection = self.connection.lock();
+        let mut statement = connection.prepare("SELECT key, value FROM settings")?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<(String, String)>, _>>()?;
+
+        if rows.is_empty() {
+            return Ok(None);
+        }
+
+        let mut settings = Map::new();
+
+        for (key, value) in rows {
+            settings.insert(key, serde_json::from_str(&value)?);
+        }
+
+        Ok(Some(Value::Object(settings)))
+    }
+
+    fn write_settings(&self, settings: &Value) -> anyhow::Result<()> {
+        let settings = settings
+            .as_object()
+            .ok_or_else(|| anyhow!("Expected the settings to be a json object"))?;
+
+        let mut connection = self.connection.lock();
+        let transaction = connection.transaction()?;
+
+        transaction.execute("DELETE FROM settings", [])?;
+
+        for (key, value) in settings {
+            transaction.execute(
+                "INSERT INTO settings (key, value) VALUES (?1, ?2)",
+                params![key, value.to_string()],
+            )?;
+        }
+
+        Ok(transaction.commit()?)
+    }
+
+    fn append_history(&self, event: &PlayEvent) -> anyhow::Result<()> {
+        insert_event(&self.connection.lock(), event)
+    }
+
+    fn read_history(&self) -> anyhow::Result<Vec<PlayEvent>> {
+        let connection = self.connection.lock();
+        let mut statement = connection.prepare(
+            "SELECT started, playlist, song_name, author, path_to_song, seconds_listened, skipped
+             FROM history ORDER BY rowid",
+        )?;
+        let events = statement
+            .query_map([], |row| {
+                // like the lines of the history log, events that cannot be
+                // read are left out
+                let started = match to_time(row.get(0)?) {
+                    Some(v) => v,
+                    None => return Ok(None),
+                };
+
+                Ok(Some(PlayEvent {
+                    started,
+                    playlist: row.get(1)?,
+                    song_name: row.get(2)?,
+                    author: row.get(3)?,
+                    path_to_song: PathBuf::from(row.get::<_, String>(4)?),
+                    seconds_listened: row.get(5)?,
+                    skipped: row.get(6)?,
+                }))
+            })?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        Ok(events)
+    }
+
+    fn write_history(&self, events: &[PlayEvent]) -> anyhow::Result<()> {
+        let mut connection = self.connection.lock();
+        let transaction = connection.transaction()?;
+
+        transaction.execute("DELETE FROM history", [])?;
+
+        for event in events {
+            insert_event(&transaction, event)?;
+        }
+
+        Ok(transaction.commit()?)
+    }
+
+    fn lock_playlist(&self, _name: &str) -> anyhow::Result<FileLock> {
+        self.lock("playlists")
+    }
+
+    fn lock_settings(&self) -> anyhow::Result<FileLock> {
+        self.lock("settings")
+    }
+}
+
+fn insert_event(connection: &Connection, event: &PlayEvent) -> anyhow::Result<()> {
+    connection.execute(
+        "INSERT INTO history (started, playlist, song_name, author, path_to_song,
+                              seconds_listened, skipped)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            event.started.0.to_rfc3339(),
+            event.playlist,
+            event.song_name,
+            event.author,
+            event.path_to_song.to_string_lossy(),
+            event.seconds_listened,
+            event.skipped,
+        ],
+    )?;
+
+    Ok(())
+}
+
+fn to_time(time: Option<String>) -> Option<LocalTime> {
+    let time = DateTime::parse_from_rfc3339(&time?).ok()?;
+
+    Some(LocalTime(time.with_timezone(&Local)))
+}
+
+fn from_time(time: &Option<LocalTime>) -> Option<String> {
+    time.as_ref().map(|time| time.0.to_rfc3339())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use serde_json::json;
+
+    use crate::{
+        cli::data::{default_sound_multiplier, Song},
+        util::{home_folder, storage::Storage},
+    };
+
+    use super::SqliteStorage;
+
+    #[test]
+    fn round_trip() -> anyhow::Result<()> {
+        let path = std::path::Path::new(&home_folder()).join("round_trip.db");
+        let _ = fs::remove_file(&path);
+        let storage = SqliteStorage::open(&path)?;
+
+        let mut song = Song::new(
+            "song".to_string(),
+            PathBuf::from("music/song.wav"),
+            Some("someone".to_string()),
+            default_sound_multiplier(),
+        );
+        song.id = "1".to_string();
+        song.tags = vec!["rock".to_string(), "live".to_string()];
+        song.start_at = Some(30.0);
+
+        storage.write_songs(&[song])?;
+        storage.write_playlist("a", &json!({ "songs": ["1", "1"], "created": null }))?;
+        storage.rename_playlist("a", "b")?;
+
+        let songs = storage.read_songs()?;
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].tags, ["rock", "live"]);
+        assert_eq!(songs[0].start_at, Some(30.0));
+        assert!(songs[0].date_added.is_some());
+
+        assert_eq!(storage.playlist_names()?, ["b"]);
+        assert_eq!(storage.read_playlist("b")?["songs"], json!(["1", "1"]));
+
+        storage.delete_playlist("b")?;
+        assert!(storage.read_playlist("b").is_err());
+
+        storage.write_settings(&json!({ "volume": 30 }))?;
+        assert_eq!(storage.read_settings()?, Some(json!({ "volume": 30 })));
+
+        fs::remove_file(&path)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use std::{fmt::Display, path::PathBuf};
+
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+use crate::cli::data::Song;
+
+use super::{history::PlayEvent, home_folder, json_storage::JsonStorage, lock::FileLock};
+
+const DATABASE_FILE: &str = "rust-cli-music_player.db";
+
+/// Where playlists, songs and settings are kept. It is the json files unless
+/// the database was created with `music storage import`
+static STORAGE: Lazy<Box<dyn Storage>> = Lazy::new(open);
+
+/// Playlists, the song library and the settings are read and written through
+/// this. Merging the changes of other processes is done on top of it, so every
+/// storage behaves the same way
+pub trait Storage: Send + Sync {
+    fn kind(&self) -> StorageKind;
+
+    /// the names of every playlist, sorted by name
+    fn playlist_names(&self) -> anyhow::Result<Vec<String>>;
+
+    /// the playlist in the layout of `info.json`. Fails if there is no such
+    /// playlist
+    fn read_playlist(&self, name: &str) -> anyhow::Result<Value>;
+
+    /// replace the playlist, or create it if it does not exist
+    fn write_playlist(&self, name: &str, playlist: &Value) -> anyhow::Result<()>;
+
+    fn delete_playlist(&self, name: &str) -> anyhow::Result<()>;
+
+    /// fails if there is a playlist with the new name already
+    fn rename_playlist(&self, name: &str, new_name: &str) -> anyhow::Result<()>;
+
+    /// every song in the song library
+    fn read_songs(&self) -> anyhow::Result<Vec<Song>>;
+
+    /// add the songs to the song library, replacing the songs with the same
+    /// id
+    fn write_songs(&self, songs: &[Song]) -> anyhow::Result<()>;
+
+    /// the settings in the layout of the settings file. `None` if they were
+    /// never saved
+    fn read_settings(&self) -> anyhow::Result<Option<Value>>;
+
+    fn write_settings(&self, settings: &Value) -> anyhow::Result<()>;
+
+    /// add the event to the end of the listening history
+    fn append_history(&self, event: &PlayEvent) -> anyhow::Result<()>;
+
+    /// the listening history, oldest first
+    fn read_history(&self) -> anyhow::Result<Vec<PlayEvent>>;
+
+    /// replace the whole listening history. Only needed to copy it between
+    /// storages
+    #[cfg(feature = "sqlite")]
+    fn write_history(&self, events: &[PlayEvent]) -> anyhow::Result<()>;
+
+    /// held while a playlist is read, merged and written back, so other
+    /// processes wait for it
+    fn lock_playlist(&self, name: &str) -> anyhow::Result<FileLock>;
+
+    /// held while the settings are read, merged and written back
+    fn lock_settings(&self) -> anyhow::Result<FileLock>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    Json,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl Display for StorageKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json => write!(f, "the json files"),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite => write!(f, "the sqlite database"),
+        }
+    }
+}
+
+/// the storage everything is read from and written into
+pub fn storage() -> &'static dyn Storage {
+    STORAGE.as_ref()
+}
+
+#[cfg(feature = "sqlite")]
+fn open() -> Box<dyn Storage> {
+    use super::sqlite_storage::SqliteStorage;
+
+    if !SqliteStorage::path().exists() {
+        return Box::new(JsonStorage);
+    }
+
+    match SqliteStorage::open(&SqliteStorage::path()) {
+        Ok(storage) => Box::new(storage),
+        Err(e) => {
+            println!("Failed to open the database! Error: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// the json files are not used anymore once everything was imported into the
+/// database, so a build without sqlite support stops instead of using them
+#[cfg(not(feature = "sqlite"))]
+fn open() -> Box<dyn Storage> {
+    let path = database_path();
+
+    if path.exists() {
+        println!(
+            "The playlists are stored in {}, but this build has no sqlite support! Build it with `--features sqlite`",
+            path.display()
+        );
+        std::process::exit(1);
+    }
+
+    Box::new(JsonStorage)
+}
+
+/// where the database is, in builds with or without sqlite support
+pub fn database_path() -> PathBuf {
+    PathBuf::from(home_folder()).join(DATABASE_FILE)
+}
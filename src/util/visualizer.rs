@@ -0,0 +1,151 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+pub const WIDTH: usize = 64;
+pub const HEIGHT: usize = 8;
+
+const BAR_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum VisualizerMode {
+    #[default]
+    Off,
+    Bars,
+    Wave,
+}
+
+impl VisualizerMode {
+    /// the mode after this one when toggling through them
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Bars,
+            Self::Bars => Self::Wave,
+            Self::Wave => Self::Off,
+        }
+    }
+}
+
+impl FromStr for VisualizerMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "bars" => Ok(Self::Bars),
+            "wave" => Ok(Self::Wave),
+            _ => Err(anyhow!(
+                "Unknown visualizer mode! Valid ones are: off, bars, wave"
+            )),
+        }
+    }
+}
+
+impl Display for VisualizerMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mode = match self {
+            Self::Off => "Off",
+            Self::Bars => "Bars",
+            Self::Wave => "Wave",
+        };
+
+        write!(f, "{}", mode)
+    }
+}
+
+/// render the fft data as a bar spectrum. Only the lower half of the data is
+/// used because that is where most of the music is
+pub fn render_bars(fft: &[f32], width: usize, height: usize) -> Vec<String> {
+    let data = &fft[..fft.len() / 2];
+    let chunk_size = (data.len() / width).max(1);
+
+    let levels = data
+        .chunks(chunk_size)
+        .take(width)
+        .map(|chunk| {
+            (chunk.iter().sum::<f32>() / chunk.len() as f32)
+                .max(0.0)
+                .sqrt()
+        })
+        .collect::<Vec<_>>();
+
+    // scale by the loudest bar so that quiet songs are still visible
+    let max = levels.iter().cloned().fold(1.0, f32::max);
+
+    (0..height)
+        .rev()
+        .map(|row| {
+            levels
+                .iter()
+                .map(|level| {
+                    let filled = level / max * height as f32 - row as f32;
+
+                    if filled >= 1.0 {
+                        BAR_BLOCKS[BAR_BLOCKS.len() - 1]
+                    } else if filled > 0.0 {
+                        BAR_BLOCKS[(filled * (BAR_BLOCKS.len() - 1) as f32) as usize]
+                    } else {
+                        ' '
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// render the wave data as an oscilloscope. Samples are expected to be in
+/// between -1 and 1
+pub fn render_wave(wave: &[f32], width: usize, height: usize) -> Vec<String> {
+    let step = (wave.len() as f32 / width as f32).max(1.0);
+
+    // the row that the sample of each column lands on
+    let sample_rows = (0..width)
+        .map_while(|column| wave.get((column as f32 * step) as usize))
+        .map(|sample| {
+            ((1.0 - sample.clamp(-1.0, 1.0)) / 2.0 * (height - 1) as f32).round() as usize
+        })
+        .collect::<Vec<_>>();
+
+    (0..height)
+        .map(|row| {
+            sample_rows
+                .iter()
+                .map(|&sample_row| if sample_row == row { '•' } else { ' ' })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{render_bars, render_wave, VisualizerMode};
+
+    #[test]
+    fn bars() {
+        let silent = render_bars(&[0.0; 256], 16, 4);
+        assert_eq!(silent.len(), 4);
+        assert!(silent.iter().all(|row| row.trim().is_empty()));
+
+        let loud = render_bars(&[4.0; 256], 16, 4);
+        assert!(loud.iter().all(|row| row == &"█".repeat(16)));
+    }
+
+    #[test]
+    fn wave() {
+        let rows = render_wave(&[0.0; 256], 32, 5);
+
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[2], "•".repeat(32));
+        assert!(rows[0].trim().is_empty());
+
+        let top = render_wave(&[1.0; 256], 8, 5);
+        assert_eq!(top[0], "•".repeat(8));
+    }
+
+    #[test]
+    fn toggle() {
+        assert_eq!(VisualizerMode::Off.next(), VisualizerMode::Bars);
+        assert_eq!(VisualizerMode::Wave.next(), VisualizerMode::Off);
+    }
+}
@@ -1,11 +1,15 @@
-use std::{path::PathBuf, process::Command, str::FromStr};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
 
 use anyhow::{anyhow, Context};
-use basic_quick_lib::home_dir::home_dir;
 
 use crate::{
     cli::data::{default_sound_multiplier, Song},
-    util::get_id_from_youtube_link,
+    util::{get_id_from_youtube_link, home_folder},
 };
 
 use super::youtube_api;
@@ -17,6 +21,30 @@ Go to http://ytdl-org.github.io/youtube-dl/download.html to download youtube-dl.
 "#;
 const DOWNLOADS_FOLDER: &str = "rust-cli-music-player_downloaded-audios";
 
+/// the folder downloaded songs are put in
+pub fn downloads_folder() -> PathBuf {
+    PathBuf::from(home_folder()).join(DOWNLOADS_FOLDER)
+}
+
+/// the id of the video a downloaded song was downloaded from. Downloaded
+/// songs are named after the id of the video
+pub fn video_id(path: &Path) -> Option<String> {
+    let path = path.to_string_lossy();
+
+    if !path.contains(DOWNLOADS_FOLDER) {
+        return None;
+    }
+
+    // the separator depends on how the path was written when downloading
+    let file_name = path.rsplit(['/', '\\']).next()?;
+    let id = file_name
+        .rsplit_once('.')
+        .map(|(id, _)| id)
+        .unwrap_or(file_name);
+
+    Some(id.to_string()).filter(|id| !id.is_empty())
+}
+
 pub struct YTDownload {
     pub output_file_name: String,
     pub audio_quality: u8,
@@ -70,6 +98,7 @@ impl YTDownload {
                 self.audio_quality.to_string().as_str(),
                 "--audio-format",
                 self.audio_extension.as_str(),
+                "--write-info-json",
                 self.link.as_str(),
             ])
             .spawn()?
@@ -97,7 +126,7 @@ impl YTDownload {
         let path = PathBuf::from_str(
             format!(
                 "{}\\{}\\{}.{}",
-                home_dir(),
+                home_folder(),
                 DOWNLOADS_FOLDER,
                 id,
                 self.audio_extension
@@ -105,14 +134,14 @@ impl YTDownload {
             .as_str(),
         )?;
 
-        Ok(Song {
-            author: video["snippet"]["channelTitle"]
+        Ok(Song::new(
+            video["snippet"]["title"].as_str().unwrap().to_string(),
+            path,
+            video["snippet"]["channelTitle"]
                 .as_str()
                 .map(|s| s.to_string()),
-            song_name: video["snippet"]["title"].as_str().unwrap().to_string(),
-            path_to_song: path,
-            sound_multiplier: default_sound_multiplier(),
-        })
+            default_sound_multiplier(),
+        ))
     }
 
     pub fn get_json(&self) -> anyhow::Result<serde_json::Value> {
@@ -121,6 +150,69 @@ impl YTDownload {
     }
 }
 
+/// gets the chapters of a downloaded video from the `info.json` yt-dlp wrote
+/// next to it while downloading, so the video is not looked up again
+pub fn get_chapters(song_path: &Path) -> anyhow::Result<Vec<Chapter>> {
+    let info_path = song_path.with_extension("info.json");
+    let json = fs::read_to_string(&info_path)
+        .with_context(|| format!("Failed to read {}", info_path.display()))?;
+
+    Ok(parse_chapters(&serde_json::from_str(&json)?))
+}
+
+/// A part of a video, in seconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    pub title: String,
+    pub start_time: f64,
+    pub end_time: Option<f64>,
+}
+
+/// read the `chapters` field of the metadata yt-dlp writes into `info.json`
+pub fn parse_chapters(json: &serde_json::Value) -> Vec<Chapter> {
+    let chapters = match json["chapters"].as_array() {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+
+    chapters
+        .iter()
+        .enumerate()
+        .filter_map(|(index, chapter)| {
+            let start_time = chapter["start_time"].as_f64()?;
+            let title = chapter["title"]
+                .as_str()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| format!("Chapter {}", index + 1));
+
+            Some(Chapter {
+                title,
+                start_time,
+                end_time: chapter["end_time"].as_f64(),
+            })
+        })
+        .collect()
+}
+
+/// split the song into one song per chapter. They all share the audio file of
+/// the song. A video without chapters stays as one song
+pub fn split_into_chapters(song: Song, chapters: &[Chapter]) -> Vec<Song> {
+    if chapters.len() < 2 {
+        return vec![song];
+    }
+
+    chapters
+        .iter()
+        .map(|chapter| Song {
+            song_name: chapter.title.clone(),
+            start_at: Some(chapter.start_time),
+            end_at: chapter.end_time,
+            ..song.clone()
+        })
+        .collect()
+}
+
 pub fn remove_cache_dir() -> anyhow::Result<()> {
     Command::new("yt-dlp")
         .arg("--rm-cache-dir")
@@ -132,7 +224,70 @@ pub fn remove_cache_dir() -> anyhow::Result<()> {
 
 #[cfg(test)]
 mod test {
-    use super::YTDownload;
+    use std::path::PathBuf;
+
+    use crate::{cli::data::Song, util::home_folder};
+
+    use super::{get_chapters, parse_chapters, split_into_chapters, Chapter, YTDownload};
+
+    #[test]
+    fn chapters() {
+        let json = serde_json::json!({
+            "title": "Full Album",
+            "chapters": [
+                { "start_time": 0.0, "end_time": 180.5, "title": "Intro" },
+                { "start_time": 180.5, "end_time": 400.0, "title": " " },
+            ]
+        });
+
+        let chapters = parse_chapters(&json);
+        assert_eq!(
+            chapters,
+            vec![
+                Chapter {
+                    title: "Intro".to_string(),
+                    start_time: 0.0,
+                    end_time: Some(180.5),
+                },
+                Chapter {
+                    title: "Chapter 2".to_string(),
+                    start_time: 180.5,
+                    end_time: Some(400.0),
+                },
+            ]
+        );
+
+        let song = Song::new(
+            "Full Album".to_string(),
+            PathBuf::from("album.wav"),
+            Some("Someone".to_string()),
+            1.0,
+        );
+        let songs = split_into_chapters(song.clone(), &chapters);
+
+        assert_eq!(songs.len(), 2);
+        assert_eq!(songs[1].song_name, "Chapter 2");
+        assert_eq!(songs[1].path_to_song, song.path_to_song);
+        assert_eq!(songs[1].author, song.author);
+        assert_eq!(songs[1].start_at, Some(180.5));
+
+        assert_eq!(split_into_chapters(song, &chapters[..1]).len(), 1);
+        assert!(parse_chapters(&serde_json::json!({ "chapters": null })).is_empty());
+    }
+
+    #[test]
+    fn chapters_are_read_from_the_info_file() -> anyhow::Result<()> {
+        let song_path = PathBuf::from(home_folder()).join("video.wav");
+        std::fs::write(
+            song_path.with_extension("info.json"),
+            r#"{ "chapters": [{ "start_time": 0.0, "title": "Intro" }] }"#,
+        )?;
+
+        assert_eq!(get_chapters(&song_path)?[0].title, "Intro");
+        assert!(get_chapters(&song_path.with_file_name("missing.wav")).is_err());
+
+        Ok(())
+    }
 
     #[test]
     fn download_exit_failed() {